@@ -0,0 +1,203 @@
+use crate::runner::{Format, Record};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// One known answer from `answers.toml`.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Expected {
+    pub day: u32,
+    pub part: u8,
+    pub input: String,
+    pub answer: String,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Manifest {
+    #[serde(default)]
+    pub answer: Vec<Expected>,
+}
+
+impl Manifest {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(text)
+    }
+
+    pub fn get(&self, day: u32, part: u8, input: &str) -> Option<&str> {
+        self.answer
+            .iter()
+            .find(|e| e.day == day && e.part == part && e.input == input)
+            .map(|e| e.answer.as_str())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    Pass,
+    Fail,
+    /// Nothing in the manifest for this day, part and input.
+    Missing,
+    /// Not run, because the part is slow or unimplemented.
+    Skipped,
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Outcome::Pass => "pass",
+            Outcome::Fail => "fail",
+            Outcome::Missing => "missing",
+            Outcome::Skipped => "skipped",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Verdict {
+    pub day: u32,
+    pub part: u8,
+    pub input: String,
+    pub outcome: Outcome,
+    pub expected: Option<String>,
+    pub actual: Option<String>,
+}
+
+impl Verdict {
+    pub fn skipped(day: u32, part: u8, input: &str, manifest: &Manifest) -> Self {
+        Self {
+            day,
+            part,
+            input: input.to_string(),
+            outcome: Outcome::Skipped,
+            expected: manifest.get(day, part, input).map(str::to_string),
+            actual: None,
+        }
+    }
+
+    /// Compare a solved part against the manifest. A record that failed to parse is a failure for
+    /// `part`, since it never produced an answer.
+    pub fn judge(manifest: &Manifest, record: &Record, part: u8) -> Self {
+        let expected = manifest.get(record.day, part, &record.input);
+        let actual = match (&record.answer, &record.error) {
+            (_, Some(err)) => err.lines().next().unwrap_or_default().to_string(),
+            (Some(answer), None) => answer.to_string(),
+            (None, None) => String::new(),
+        };
+        let outcome = match expected {
+            None => Outcome::Missing,
+            Some(e) if record.error.is_none() && e == actual => Outcome::Pass,
+            Some(_) => Outcome::Fail,
+        };
+        Self {
+            day: record.day,
+            part,
+            input: record.input.clone(),
+            outcome,
+            expected: expected.map(str::to_string),
+            actual: Some(actual),
+        }
+    }
+}
+
+pub fn write_verdicts(
+    out: &mut impl Write,
+    verdicts: &[Verdict],
+    format: Format,
+) -> io::Result<()> {
+    match format {
+        Format::Plain => {
+            for v in verdicts {
+                write!(out, "day {:>2} part {}: {}", v.day, v.part, v.outcome)?;
+                if v.outcome == Outcome::Fail {
+                    write!(
+                        out,
+                        ", expected {} got {}",
+                        v.expected.as_deref().unwrap_or_default(),
+                        v.actual.as_deref().unwrap_or_default()
+                    )?;
+                }
+                writeln!(out)?;
+            }
+            let count = |o| verdicts.iter().filter(|v| v.outcome == o).count();
+            writeln!(
+                out,
+                "{} passed, {} failed, {} missing, {} skipped",
+                count(Outcome::Pass),
+                count(Outcome::Fail),
+                count(Outcome::Missing),
+                count(Outcome::Skipped)
+            )?;
+        }
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut *out, verdicts)?;
+            writeln!(out)?;
+        }
+        Format::Tsv => {
+            writeln!(out, "day\tpart\tinput\toutcome\texpected\tactual")?;
+            for v in verdicts {
+                writeln!(
+                    out,
+                    "{}\t{}\t{}\t{}\t{}\t{}",
+                    v.day,
+                    v.part,
+                    v.input,
+                    v.outcome,
+                    v.expected.as_deref().unwrap_or_default(),
+                    v.actual.as_deref().unwrap_or_default()
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::runner::{self, Source};
+
+    #[test]
+    fn test_verdicts() {
+        let manifest = Manifest::parse(
+            r#"
+            [[answer]]
+            day = 9
+            part = 1
+            input = "09a.txt"
+            answer = "114"
+
+            [[answer]]
+            day = 9
+            part = 2
+            input = "09a.txt"
+            answer = "3"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(manifest.get(9, 1, "09a.txt"), Some("114"));
+        assert_eq!(manifest.get(9, 1, "day9.txt"), None);
+
+        let day = crate::day(9).unwrap();
+        let text = std::fs::read_to_string("input/2023/09a.txt").unwrap();
+        let records = runner::solve(day, &Source::new("09a.txt", text), &[1, 2]).unwrap();
+        let outcomes: Vec<_> = records
+            .iter()
+            .map(|r| Verdict::judge(&manifest, r, r.part.unwrap()).outcome)
+            .collect();
+        assert_eq!(outcomes, vec![Outcome::Pass, Outcome::Fail]);
+
+        let other = Source::new("other.txt", "1 2 3".to_string());
+        let records = runner::solve(day, &other, &[1]).unwrap();
+        let verdict = Verdict::judge(&manifest, &records[0], 1);
+        assert_eq!(verdict.outcome, Outcome::Missing);
+    }
+}
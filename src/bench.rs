@@ -0,0 +1,270 @@
+use crate::parse::ParseError;
+use crate::runner::{Format, Source};
+use crate::solution::Day;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// A new median has to be this much slower than the last one before it counts as a regression.
+const REGRESSION_RATIO: f64 = 1.10;
+
+/// Median and median absolute deviation of some timings, both in nanoseconds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Stats {
+    pub runs: usize,
+    pub median_ns: u64,
+    pub mad_ns: u64,
+}
+
+fn median(sorted: &[u64]) -> u64 {
+    let n = sorted.len();
+    if n % 2 == 1 {
+        sorted[n / 2]
+    } else {
+        (sorted[n / 2 - 1] + sorted[n / 2]) / 2
+    }
+}
+
+impl Stats {
+    pub fn new(mut samples: Vec<u64>) -> Self {
+        assert!(!samples.is_empty(), "no samples");
+        samples.sort_unstable();
+        let median_ns = median(&samples);
+        let mut deviations: Vec<u64> = samples.iter().map(|s| s.abs_diff(median_ns)).collect();
+        deviations.sort_unstable();
+        Self {
+            runs: samples.len(),
+            median_ns,
+            mad_ns: median(&deviations),
+        }
+    }
+
+    /// Slower than `previous` by more than both the noise in either measurement and
+    /// `REGRESSION_RATIO`.
+    pub fn regressed_from(&self, previous: &Stats) -> bool {
+        let noise = 3 * self.mad_ns.max(previous.mad_ns);
+        self.median_ns as f64 > previous.median_ns as f64 * REGRESSION_RATIO
+            && self.median_ns > previous.median_ns + noise
+    }
+}
+
+/// One line of the history file: how long one stage of one day took on one input.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Entry {
+    /// Seconds since the epoch.
+    pub timestamp: u64,
+    pub day: u32,
+    /// `parse`, `part1` or `part2`.
+    pub stage: String,
+    pub input_hash: String,
+    #[serde(flatten)]
+    pub stats: Stats,
+}
+
+/// How many times to repeat each stage. Stops early once `budget` is used up, after at least
+/// one run, so the slow days don't hold everything up.
+#[derive(Clone, Copy, Debug)]
+pub struct Settings {
+    pub runs: usize,
+    pub budget: Duration,
+}
+
+fn sample<T>(settings: Settings, mut f: impl FnMut() -> T) -> Stats {
+    let start = Instant::now();
+    let mut samples = vec![];
+    while samples.is_empty() || (samples.len() < settings.runs && start.elapsed() < settings.budget)
+    {
+        let t = Instant::now();
+        let output = f();
+        samples.push(t.elapsed().as_nanos() as u64);
+        drop(output);
+    }
+    Stats::new(samples)
+}
+
+/// Time parsing and then each of `parts` separately.
+pub fn bench(
+    day: &Day,
+    source: &Source,
+    parts: &[u8],
+    settings: Settings,
+) -> Result<Vec<Entry>, ParseError> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let entry = |stage: String, stats| Entry {
+        timestamp,
+        day: day.day,
+        stage,
+        input_hash: source.hash.clone(),
+        stats,
+    };
+
+    // Parse once up front so a bad input is an error rather than a timing
+    let input = day.parse(&source.text)?;
+    let mut entries = vec![entry(
+        "parse".to_string(),
+        sample(settings, || day.parse(&source.text)),
+    )];
+    for &part in parts {
+        let stats = sample(settings, || day.solve(input.as_ref(), part));
+        entries.push(entry(format!("part{}", part), stats));
+    }
+    Ok(entries)
+}
+
+/// Everything in the history file, oldest first. A missing file is an empty history.
+pub fn load_history(path: &Path) -> io::Result<Vec<Entry>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e),
+    };
+    text.lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| serde_json::from_str(l).map_err(io::Error::from))
+        .collect()
+}
+
+/// Append `entries` to the history file, one JSON object per line.
+pub fn save_history(path: &Path, entries: &[Entry]) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    for entry in entries {
+        writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    }
+    Ok(())
+}
+
+/// A fresh measurement next to the latest earlier one for the same day, stage and input.
+#[derive(Clone, Debug, Serialize)]
+pub struct Comparison {
+    #[serde(flatten)]
+    pub entry: Entry,
+    pub previous: Option<Stats>,
+    pub regression: bool,
+}
+
+pub fn compare(history: &[Entry], entries: Vec<Entry>) -> Vec<Comparison> {
+    entries
+        .into_iter()
+        .map(|entry| {
+            let previous = history
+                .iter()
+                .rev()
+                .find(|h| {
+                    h.day == entry.day && h.stage == entry.stage && h.input_hash == entry.input_hash
+                })
+                .map(|h| h.stats);
+            let regression = previous.is_some_and(|p| entry.stats.regressed_from(&p));
+            Comparison {
+                entry,
+                previous,
+                regression,
+            }
+        })
+        .collect()
+}
+
+pub fn write_comparisons(
+    out: &mut impl Write,
+    comparisons: &[Comparison],
+    format: Format,
+) -> io::Result<()> {
+    let ns = Duration::from_nanos;
+    match format {
+        Format::Plain => {
+            for c in comparisons {
+                let e = &c.entry;
+                write!(
+                    out,
+                    "day {:>2} {:<5}  {:>12} ± {:<12} ({} runs)",
+                    e.day,
+                    e.stage,
+                    format!("{:.1?}", ns(e.stats.median_ns)),
+                    format!("{:.1?}", ns(e.stats.mad_ns)),
+                    e.stats.runs
+                )?;
+                if let Some(p) = c.previous {
+                    let change = e.stats.median_ns as f64 / p.median_ns.max(1) as f64 - 1.0;
+                    write!(out, "  {:+.1}% vs {:.1?}", change * 100.0, ns(p.median_ns))?;
+                }
+                if c.regression {
+                    write!(out, "  REGRESSION")?;
+                }
+                writeln!(out)?;
+            }
+        }
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut *out, comparisons)?;
+            writeln!(out)?;
+        }
+        Format::Tsv => {
+            writeln!(
+                out,
+                "day\tstage\tinput_hash\truns\tmedian_ns\tmad_ns\tprevious_median_ns\tregression"
+            )?;
+            for c in comparisons {
+                let e = &c.entry;
+                writeln!(
+                    out,
+                    "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                    e.day,
+                    e.stage,
+                    e.input_hash,
+                    e.stats.runs,
+                    e.stats.median_ns,
+                    e.stats.mad_ns,
+                    c.previous
+                        .map(|p| p.median_ns.to_string())
+                        .unwrap_or_default(),
+                    c.regression
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_stats() {
+        let stats = Stats::new(vec![9, 1, 4, 100, 5]);
+        assert_eq!((stats.median_ns, stats.mad_ns), (5, 4));
+        let stats = Stats::new(vec![10, 20]);
+        assert_eq!((stats.median_ns, stats.mad_ns), (15, 5));
+    }
+
+    #[test]
+    fn test_regression() {
+        let entry = |timestamp, median_ns, mad_ns| Entry {
+            timestamp,
+            day: 5,
+            stage: "part2".to_string(),
+            input_hash: "abc".to_string(),
+            stats: Stats {
+                runs: 10,
+                median_ns,
+                mad_ns,
+            },
+        };
+        let history = vec![entry(1, 2000, 10), entry(2, 1000, 10)];
+
+        // Compared against the latest run, not the slowest
+        let c = compare(&history, vec![entry(3, 1500, 10)]);
+        assert_eq!(c[0].previous.unwrap().median_ns, 1000);
+        assert!(c[0].regression);
+        // Within the noise
+        assert!(!compare(&history, vec![entry(3, 1500, 200)])[0].regression);
+        // Within 10%
+        assert!(!compare(&history, vec![entry(3, 1090, 1)])[0].regression);
+
+        let line = serde_json::to_string(&history[0]).unwrap();
+        assert_eq!(serde_json::from_str::<Entry>(&line).unwrap(), history[0]);
+    }
+}
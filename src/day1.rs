@@ -1,3 +1,6 @@
+use crate::parse::ParseError;
+use crate::solution::{Answer, Solution};
+
 fn check(asdf: &str, dumb: bool) -> Option<u32> {
     //println!("asdf: {}", asdf);
     if dumb {
@@ -55,6 +58,26 @@ pub fn part2(input: &str) -> usize {
     parse(input, true).into_iter().map(|x| x as usize).sum()
 }
 
+pub struct Day1;
+
+impl Solution for Day1 {
+    type Input = String;
+
+    const DAY: u32 = 1;
+
+    fn parse(input: &str) -> Result<String, ParseError> {
+        Ok(load_input(input))
+    }
+
+    fn part1(input: &String) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &String) -> Answer {
+        part2(input).into()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -1,568 +1,167 @@
-use aoc_helpers::graph::Graph;
-use std::collections::{HashMap, HashSet};
-use uuid::Uuid;
+use crate::direction::Direction;
+use crate::grid::Grid;
+use crate::image::{Overlay, Picture};
+use crate::parse::{self, ParseError};
+use crate::solution::{Answer, Solution};
+use crate::viewer::{Palette, Rgb};
+use std::collections::HashSet;
 
-type Input = (HashMap<(i64, i64), (char, Uuid)>, Graph<(i64, i64, Tile)>);
+// The grid, and the main loop's tiles in order starting from `S`.
+type Input = (Grid<char>, Vec<(usize, usize)>);
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
-pub enum Tile {
-    V,
-    H,
-    NE,
-    NW,
-    SW,
-    SE,
-    G,
-    S,
-}
-
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-enum Direction {
-    North,
-    South,
-    East,
-    West,
-}
-
-fn add_edge(
-    x: i64,
-    y: i64,
-    map: &HashMap<(i64, i64), (char, Uuid)>,
-    graph: &mut Graph<(i64, i64, Tile)>,
-    dir: Direction,
-) {
-    // We just assume this is valid here
-    let &(_, node1) = map.get(&(x, y)).unwrap();
-
-    match dir {
-        Direction::North => {
-            // above
-            if y > 0 {
-                if let Some(&(t, node2)) = map.get(&(x, y - 1)) {
-                    if t == '|' || t == '7' || t == 'F' {
-                        graph.add_edge(node1, node2);
-                    }
-                }
-            }
-        }
-        Direction::South => {
-            // below
-            if let Some(&(t, node2)) = map.get(&(x, y + 1)) {
-                if t == '|' || t == 'L' || t == 'J' {
-                    graph.add_edge(node1, node2);
-                }
-            }
-        }
-        Direction::East => {
-            // right
-            if let Some(&(t, node2)) = map.get(&(x + 1, y)) {
-                if t == '-' || t == 'J' || t == '7' {
-                    graph.add_edge(node1, node2);
-                }
-            }
-        }
-        Direction::West => {
-            // left
-            if x > 0 {
-                if let Some(&(t, node2)) = map.get(&(x - 1, y)) {
-                    if t == '-' || t == 'L' || t == 'F' {
-                        graph.add_edge(node1, node2);
-                    }
-                }
-            }
-        }
-    }
-}
-
-fn add_edge_corner(
-    x: i64,
-    y: i64,
-    map: &HashMap<(i64, i64), (char, Uuid)>,
-    graph: &mut Graph<(i64, i64, Tile)>,
-    dir: Direction,
-) {
-    // We just assume this is valid here
-    let &(_, node1) = map.get(&(x, y)).unwrap();
-
-    match dir {
-        // F-7
-        // |.|
-        // L-J
-        Direction::North => {
-            // above
-            if y > 0 {
-                if let Some(&(t, node2)) = map.get(&(x, y - 1)) {
-                    if t == '|' || t == 'L' || t == 'F' || t == '.' {
-                        graph.add_edge(node1, node2);
-                    }
-                }
-            }
-        }
-        Direction::South => {
-            // below
-            if let Some(&(t, node2)) = map.get(&(x, y + 1)) {
-                if t == '|' || t == 'L' || t == 'F' || t == '.' {
-                    graph.add_edge(node1, node2);
-                }
-            }
-        }
-        Direction::East => {
-            // right
-            if let Some(&(t, node2)) = map.get(&(x + 1, y)) {
-                if t == '-' || t == 'F' || t == '7' || t == '.' {
-                    graph.add_edge(node1, node2);
-                }
-            }
-        }
-        Direction::West => {
-            // left
-            if x > 0 {
-                if let Some(&(t, node2)) = map.get(&(x - 1, y)) {
-                    if t == '-' || t == '7' || t == 'F' || t == '.' {
-                        graph.add_edge(node1, node2);
-                    }
-                }
-            }
+#[aoc_generator(day10)]
+pub fn load_input(input: &str) -> Result<Input, ParseError> {
+    let lines: Vec<_> = parse::lines(10, input).collect();
+    let grid = Grid::parse(lines.iter().copied(), "|-LJ7F.S")?;
+    // Every tile is ASCII, so x is also the byte offset into its line
+    let error = |(x, y): (usize, usize), expected: &str| {
+        let line = lines[y];
+        line.error(&line.text[x..], expected)
+    };
+    let starts: Vec<_> = grid.positions().filter(|&p| grid[p] == 'S').collect();
+    let Some(&start) = starts.first() else {
+        return Err(ParseError::new(10, 1, 1, "an `S` tile", ""));
+    };
+    if let Some(&other) = starts.get(1) {
+        return Err(error(other, "only one `S` tile"));
+    }
+    let main_loop = trace_loop(&grid, start).map_err(|at| {
+        if at == start {
+            error(at, "an `S` joined to exactly two pipes")
+        } else {
+            error(at, "a pipe that joins up with the next one")
         }
-    }
-}
-
-fn add_edges_using_map(g: &mut Graph<(i64, i64, Tile)>, map: &HashMap<(i64, i64), (char, Uuid)>) {
-    // Add edges
-    for (k, v) in map.iter() {
-        let &(x, y) = k;
-        let &(c, _) = v;
-        match c {
-            '|' => {
-                // Vertical
-                add_edge(x, y, map, g, Direction::North);
-                add_edge(x, y, map, g, Direction::South);
-            }
-            '-' => {
-                // Horizontal
-                add_edge(x, y, map, g, Direction::East);
-                add_edge(x, y, map, g, Direction::West);
-            }
-            'L' => {
-                // 90 deg. bend connecting North and East
-                // above
-                add_edge(x, y, map, g, Direction::North);
-                add_edge(x, y, map, g, Direction::East);
-            }
-            'J' => {
-                // 90 deg. bend connecting North and West
-                // above
-                add_edge(x, y, map, g, Direction::North);
-                add_edge(x, y, map, g, Direction::West);
-            }
-            '7' => {
-                // 90 deg. bend connecting South and West
-                // above
-                add_edge(x, y, map, g, Direction::South);
-                add_edge(x, y, map, g, Direction::West);
-            }
-            'F' => {
-                // 90 deg. bend connecting South and East
-                // above
-                add_edge(x, y, map, g, Direction::South);
-                add_edge(x, y, map, g, Direction::East);
-            }
-            '.' => (),
-            'S' => {
-                // TODO: Have to figure out what this is somehow
-                add_edge(x, y, map, g, Direction::North);
-                add_edge(x, y, map, g, Direction::South);
-                add_edge(x, y, map, g, Direction::East);
-                add_edge(x, y, map, g, Direction::West);
-            }
-            _ => (),
-        };
-    }
+    })?;
+    Ok((grid, main_loop))
 }
 
-fn add_edges_using_map_corners(
-    g: &mut Graph<(i64, i64, Tile)>,
-    map: &HashMap<(i64, i64), (char, Uuid)>,
-) {
-    // Add edges
-    for (k, v) in map.iter() {
-        let &(x, y) = k;
-        let &(c, _) = v;
-        // F-7
-        // | |
-        // L-J
-        match c {
-            '|' => {
-                // Vertical
-                add_edge_corner(x, y, map, g, Direction::North);
-                add_edge_corner(x, y, map, g, Direction::South);
-                add_edge_corner(x, y, map, g, Direction::West);
-            }
-            '-' => {
-                // Horizontal
-                add_edge_corner(x, y, map, g, Direction::North);
-                add_edge_corner(x, y, map, g, Direction::East);
-                add_edge_corner(x, y, map, g, Direction::West);
-            }
-            'L' => {
-                // 90 deg. bend connecting North and East
-                // above
-                add_edge_corner(x, y, map, g, Direction::North);
-                add_edge_corner(x, y, map, g, Direction::South);
-                add_edge_corner(x, y, map, g, Direction::West);
-            }
-            'J' => {
-                // 90 deg. bend connecting North and West
-                // above
-                add_edge_corner(x, y, map, g, Direction::North);
-                add_edge_corner(x, y, map, g, Direction::West);
-            }
-            '7' => {
-                // 90 deg. bend connecting South and West
-                // above
-                add_edge_corner(x, y, map, g, Direction::North);
-                add_edge_corner(x, y, map, g, Direction::West);
-                add_edge_corner(x, y, map, g, Direction::East);
-            }
-            'F' => {
-                // 90 deg. bend connecting South and East
-                // above
-                add_edge_corner(x, y, map, g, Direction::North);
-                add_edge_corner(x, y, map, g, Direction::South);
-                add_edge_corner(x, y, map, g, Direction::West);
-                add_edge_corner(x, y, map, g, Direction::East);
-            }
-            '.' => {
-                add_edge_corner(x, y, map, g, Direction::North);
-                add_edge_corner(x, y, map, g, Direction::South);
-                add_edge_corner(x, y, map, g, Direction::East);
-                add_edge_corner(x, y, map, g, Direction::West);
-            }
-            'S' => {
-                // TODO: Have to figure out what this is somehow
-            }
-            _ => (),
-        };
+// The ways out of a pipe.
+fn openings(c: char) -> &'static [Direction] {
+    match c {
+        '|' => &[Direction::North, Direction::South],
+        '-' => &[Direction::East, Direction::West],
+        'L' => &[Direction::North, Direction::East],
+        'J' => &[Direction::North, Direction::West],
+        '7' => &[Direction::South, Direction::West],
+        'F' => &[Direction::South, Direction::East],
+        _ => &[],
     }
 }
 
-#[aoc_generator(day10)]
-pub fn load_input(input: &str) -> Input {
-    let mut map: HashMap<(i64, i64), (char, Uuid)> = HashMap::new();
-    let mut g = Graph::<(i64, i64, Tile)>::new();
-
-    // Add nodes
-    for (y, line) in input.lines().enumerate() {
-        for (x, c) in line.chars().enumerate() {
-            let maybe_node = match c {
-                '|' => Some(g.add_node_with_value((x as i64, y as i64, Tile::V))),
-                '-' => Some(g.add_node_with_value((x as i64, y as i64, Tile::H))),
-                'L' => Some(g.add_node_with_value((x as i64, y as i64, Tile::NE))),
-                'J' => Some(g.add_node_with_value((x as i64, y as i64, Tile::NW))),
-                '7' => Some(g.add_node_with_value((x as i64, y as i64, Tile::SW))),
-                'F' => Some(g.add_node_with_value((x as i64, y as i64, Tile::SE))),
-                '.' => Some(g.add_node_with_value((x as i64, y as i64, Tile::G))),
-                'S' => Some(g.add_node_with_value((x as i64, y as i64, Tile::S))),
-                _ => None,
-            };
-            if let Some(node) = maybe_node {
-                map.insert((x as i64, y as i64), (c, node));
-            }
-        }
-    }
-    (map, g)
+// Which ways `S` connects, going by the pipes around it.
+fn start_openings(input: &Grid<char>, start: (usize, usize)) -> Vec<Direction> {
+    Direction::ALL
+        .into_iter()
+        .filter(|&dir| {
+            input
+                .step(start, dir, 1)
+                .is_some_and(|n| openings(input[n]).contains(&dir.reverse()))
+        })
+        .collect()
 }
 
-#[aoc(day10, part1)]
-pub fn part1(input: &Input) -> i64 {
-    let map = input.0.clone();
-    let mut graph = input.1.clone();
-    add_edges_using_map(&mut graph, &map);
-    let start = graph.nodes.iter().find(|n| n.value.2 == Tile::S).unwrap();
-
-    let mut frontier: HashSet<Uuid> = graph.get_node_neighbors(start.id);
-    let mut explored: HashSet<Uuid> = HashSet::new();
-    let mut distmap: HashMap<(i64, i64), i64> = HashMap::new();
-    let mut dist = 1;
+/// The tiles of the main loop in order, starting from `start`. Errors with the tile whose pipe
+/// leads nowhere, or `start` itself if it isn't joined to exactly two pipes.
+fn trace_loop(
+    input: &Grid<char>,
+    start: (usize, usize),
+) -> Result<Vec<(usize, usize)>, (usize, usize)> {
+    let [mut dir, _] = start_openings(input, start)[..] else {
+        return Err(start);
+    };
+    let mut pos = start;
+    let mut output = vec![];
     loop {
-        if frontier.is_empty() {
+        output.push(pos);
+        let next = input
+            .step(pos, dir, 1)
+            .filter(|&n| n == start || openings(input[n]).contains(&dir.reverse()))
+            .ok_or(pos)?;
+        if next == start {
             break;
         }
-        let mut new_frontier = HashSet::new();
-        for id in &frontier {
-            let node = graph.get_node_from_id(*id).unwrap();
-            distmap.insert((node.value.0, node.value.1), dist);
-
-            // While we're here, search neighbors of this frontier node for _new_ frontier
-            for nid in &graph.get_node_neighbors(*id) {
-                if !explored.contains(nid) && !frontier.contains(nid) {
-                    // If `nid` not in `explored` or current `frontier` then it is a hit
-                    new_frontier.insert(*nid);
-                }
-            }
-        }
-        dist += 1;
-
-        // Move old `frontier` into `explored`
-        explored = explored.into_iter().chain(frontier).collect();
-
-        // Set `new_frontier` to `frontier` for next iteration
-        frontier = new_frontier.clone();
-    }
-
-    *distmap.values().max().unwrap()
-}
-
-fn char_to_tile(c: char) -> Tile {
-    match c {
-        '|' => Tile::V,
-        '-' => Tile::H,
-        'L' => Tile::NE,
-        'J' => Tile::NW,
-        '7' => Tile::SW,
-        'F' => Tile::SE,
-        '.' => Tile::G,
-        'S' => Tile::S,
-        _ => panic!("Invalid char"),
+        pos = next;
+        // Leave by whichever opening we didn't come in through
+        dir = *openings(input[pos])
+            .iter()
+            .find(|&&d| d != dir.reverse())
+            .ok_or(pos)?;
     }
+    Ok(output)
 }
 
-// Draw '.' all around border of original input like:
-// ..............
-// . Input Here .
-// ..............
-fn extended_input(input: &Input, maybe_main_loop: Option<Vec<(i64, i64)>>) -> Input {
-    let (map, _) = input;
-
-    let xmax = *map.keys().map(|(x, _)| x).max().unwrap();
-    let ymax = *map.keys().map(|(_, y)| y).max().unwrap();
-
-    let mut newmap = HashMap::new();
-    let mut newgraph = Graph::<(i64, i64, Tile)>::new();
-    for (k, v) in map {
-        if let Some(main_loop) = maybe_main_loop.clone() {
-            if main_loop.contains(&(k.0 + 1, k.1 + 1)) {
-                let node = newgraph.add_node_with_value((k.0 + 1, k.1 + 1, char_to_tile(v.0)));
-                newmap.insert((k.0 + 1, k.1 + 1), (v.0, node));
-            } else {
-                // If not part of the main_loop just replace it with '.'
-                let node = newgraph.add_node_with_value((k.0 + 1, k.1 + 1, Tile::G));
-                newmap.insert((k.0 + 1, k.1 + 1), ('.', node));
+/// Tiles inside the loop, by counting how many times each row crosses it.
+pub fn enclosed(input: &Grid<char>, main_loop: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let on_loop: HashSet<_> = main_loop.iter().copied().collect();
+    let start = main_loop[0];
+    let start_north = start_openings(input, start).contains(&Direction::North);
+    let mut output = vec![];
+    for y in 0..input.height() {
+        let mut inside = false;
+        for x in 0..input.width() {
+            let c = input[(x, y)];
+            if on_loop.contains(&(x, y)) {
+                // Only count pipes going north, so a run like `L--7` crosses once and `L--J` not at
+                // all
+                if openings(c).contains(&Direction::North) || c == 'S' && start_north {
+                    inside = !inside;
+                }
+            } else if inside {
+                output.push((x, y));
             }
-        } else {
-            let node = newgraph.add_node_with_value((k.0 + 1, k.1 + 1, char_to_tile(v.0)));
-            newmap.insert((k.0 + 1, k.1 + 1), (v.0, node));
         }
     }
+    output
+}
 
-    // Northern edge
-    for x in 0..xmax + 3 {
-        let node = newgraph.add_node_with_value((x, 0, Tile::G));
-        newmap.insert((x, 0), ('.', node));
-    }
-
-    // Southern edge
-    for x in 0..xmax + 3 {
-        let node = newgraph.add_node_with_value((x, ymax + 2, Tile::G));
-        newmap.insert((x, ymax + 2), ('.', node));
-    }
-
-    // Western edge
-    for y in 1..ymax + 2 {
-        let node = newgraph.add_node_with_value((0, y, Tile::G));
-        newmap.insert((0, y), ('.', node));
-    }
-
-    // Eastern edge
-    for y in 1..ymax + 2 {
-        let node = newgraph.add_node_with_value((xmax + 2, y, Tile::G));
-        newmap.insert((xmax + 2, y), ('.', node));
-    }
-
-    // Add back in the edges
-    if maybe_main_loop.is_some() {
-        add_edges_using_map_corners(&mut newgraph, &newmap);
-    } else {
-        add_edges_using_map(&mut newgraph, &newmap);
-    }
-    (newmap, newgraph)
+#[aoc(day10, part1)]
+pub fn part1(input: &Input) -> usize {
+    // The far side of the loop is halfway round it either way
+    input.1.len() / 2
 }
 
-// Instead of graph of centers, what about graph of corners?
 #[aoc(day10, part2)]
-pub fn part2(input: &Input) -> i64 {
-    // Solve part 1 to get the loop...
-    let main_loop: Vec<_>;
-    {
-        let (_, graph) = extended_input(input, None);
-        let start = graph.nodes.iter().find(|n| n.value.2 == Tile::S).unwrap();
-
-        let mut frontier: HashSet<Uuid> = graph.get_node_neighbors(start.id);
-        let mut explored: HashSet<Uuid> = HashSet::new();
-        let mut distmap: HashMap<(i64, i64), i64> = HashMap::new();
-        let mut dist = 1;
-        loop {
-            if frontier.is_empty() {
-                break;
-            }
-            let mut new_frontier = HashSet::new();
-            for id in &frontier {
-                let node = graph.get_node_from_id(*id).unwrap();
-                distmap.insert((node.value.0, node.value.1), dist);
-
-                // While we're here, search neighbors of this frontier node for _new_ frontier
-                for nid in &graph.get_node_neighbors(*id) {
-                    if !explored.contains(nid) && !frontier.contains(nid) {
-                        // If `nid` not in `explored` or current `frontier` then it is a hit
-                        new_frontier.insert(*nid);
-                    }
-                }
-            }
-            dist += 1;
-
-            // Move old `frontier` into `explored`
-            explored = explored.into_iter().chain(frontier).collect();
-
-            // Set `new_frontier` to `frontier` for next iteration
-            frontier = new_frontier.clone();
-        }
-        // Verify part 1 still good
-        main_loop = explored
-            .iter()
-            .map(|u| {
-                let node = graph.get_node_from_id(*u).unwrap();
-                (node.value.0, node.value.1)
-            })
-            .collect();
-        /*
-        println!("Main Loop:");
-        print_map_board(&main_loop, &map);
-        println!();
-        */
-    }
+pub fn part2(input: &Input) -> usize {
+    let (grid, main_loop) = input;
+    enclosed(grid, main_loop).len()
+}
 
-    // Lets extend the border by 1 in all directions with '.', then start a water filling from
-    // (0, 0)
-    let (map, graph) = extended_input(input, Some(main_loop.clone()));
-    /*
-    println!("Extended Input:");
-    print_board(&map);
-    println!();
-    */
-    let start = graph
-        .nodes
-        .iter()
-        .find(|n| n.value.0 == 0 && n.value.1 == 0)
-        .unwrap();
+pub struct Day10;
 
-    //println!("graph: {:?}", graph.edges);
-    let mut frontier: HashSet<Uuid> = graph.get_node_neighbors(start.id);
-    //println!("start neighbors: {:?}", frontier);
-    let mut explored: HashSet<Uuid> = HashSet::new();
-    loop {
-        //println!("frontier.len(): {}", frontier.len());
-        if frontier.is_empty() {
-            break;
-        }
-        let mut new_frontier = HashSet::new();
-        for id in &frontier {
-            // Search neighbors of this frontier node for _new_ frontier
-            for nid in &graph.get_node_neighbors(*id) {
-                if !explored.contains(nid) && !frontier.contains(nid) {
-                    // If `nid` not in `explored` or current `frontier` then it is a hit
-                    new_frontier.insert(*nid);
-                }
-            }
-        }
+impl Solution for Day10 {
+    type Input = Input;
 
-        // Move old `frontier` into `explored`
-        explored = explored.into_iter().chain(frontier).collect();
+    const DAY: u32 = 10;
 
-        // Set `new_frontier` to `frontier` for next iteration
-        frontier = new_frontier.clone();
-        /*
-        {
-            let asdf: Vec<_> = explored
-                .iter()
-                .map(|u| {
-                    let node = graph.get_node_from_id(*u).unwrap();
-                    (node.value.0, node.value.1)
-                })
-                .collect();
-            // DEBUG
-            println!("Exterior Map:");
-            print_map_board(&asdf, &map);
-            println!();
-        }
-        */
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        load_input(input)
     }
 
-    let asdf: Vec<_> = explored
-        .iter()
-        .map(|u| {
-            let node = graph.get_node_from_id(*u).unwrap();
-            (node.value.0, node.value.1)
-        })
-        .collect();
-    /*
-    {
-        // DEBUG
-        println!("Exterior Map:");
-        print_map_board(&asdf, &map);
-        println!();
+    fn part1(input: &Input) -> Answer {
+        part1(input).into()
     }
-    */
 
-    let main_loop: HashSet<(i64, i64)> = main_loop.into_iter().collect();
-    let exterior: HashSet<(i64, i64)> = asdf.into_iter().collect();
-
-    let answer_complement: Vec<_> = main_loop.union(&exterior).copied().collect();
-    /*
-    {
-        // DEBUG
-        println!("Union Map:");
-        print_map_board(&answer_complement, &map);
-        println!();
+    fn part2(input: &Input) -> Answer {
+        part2(input).into()
     }
-    */
-
-    let xmax = map.keys().map(|(x, _)| x).max().unwrap();
-    let ymax = map.keys().map(|(_, y)| y).max().unwrap();
-
-    (xmax + 1) * (ymax + 1) - answer_complement.len() as i64
-}
 
-#[allow(dead_code)]
-fn print_map_board(map: &[(i64, i64)], tilemap: &HashMap<(i64, i64), (char, Uuid)>) {
-    let xmax = *tilemap.keys().map(|(x, _)| x).max().unwrap();
-    let ymax = *tilemap.keys().map(|(_, y)| y).max().unwrap();
-    for y in 0..ymax + 1 {
-        for x in 0..xmax + 1 {
-            let mut color = false;
-            if map.contains(&(x, y)) {
-                color = true;
-            }
-            let (cc, _) = tilemap.get(&(x, y)).unwrap();
-            if color {
-                print!("\x1b[91m{}\x1b[0m", *cc);
-            } else {
-                print!("{}", *cc);
-            }
-        }
-        println!();
-    }
-}
-
-#[allow(dead_code)]
-fn print_board(tilemap: &HashMap<(i64, i64), (char, Uuid)>) {
-    let xmax = *tilemap.keys().map(|(x, _)| x).max().unwrap();
-    let ymax = *tilemap.keys().map(|(_, y)| y).max().unwrap();
-    for y in 0..ymax + 1 {
-        for x in 0..xmax + 1 {
-            let (c, _) = tilemap.get(&(x, y)).unwrap();
-            print!("{}", c);
-        }
-        println!();
+    fn picture(input: &Input) -> Option<Picture> {
+        let (grid, main_loop) = input;
+        let inside = enclosed(grid, main_loop);
+        let palette = Palette::new(&[('.', Rgb(30, 30, 30))]).with_highlight(Rgb(80, 200, 120));
+        let color = palette.highlight;
+        let mut closed = main_loop.clone();
+        closed.push(main_loop[0]);
+        let picture = Picture::new(grid.clone(), palette)
+            .with_overlay(Overlay::Cells {
+                cells: inside,
+                color,
+            })
+            .with_overlay(Overlay::Path {
+                points: closed,
+                color: Rgb(255, 200, 60),
+            });
+        Some(picture)
     }
 }
 
@@ -574,30 +173,49 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2023/10a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 4);
 
         let input = read_to_string("input/2023/10b.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 8);
+        assert_eq!(input.1.len(), 2 * 8);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2023/10c.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 4);
 
         let input = read_to_string("input/2023/10d.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 4);
 
         let input = read_to_string("input/2023/10e.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 8);
 
         let input = read_to_string("input/2023/10f.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 10);
+        assert_eq!(enclosed(&input.0, &input.1).len(), 10);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(load_input("").unwrap_err().expected, "an `S` tile");
+        assert_eq!(load_input("...\n.|.").unwrap_err().expected, "an `S` tile");
+        let err = load_input("S7.\nLJS").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 3));
+        // `S` only has the one pipe going into it
+        let err = load_input("S-.\n...").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 1));
+        // The loop runs off the bottom of the grid
+        let err = load_input("S7\n||").unwrap_err();
+        assert_eq!(
+            (err.line, err.column, err.expected.as_str()),
+            (2, 2, "a pipe that joins up with the next one")
+        );
     }
 }
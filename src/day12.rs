@@ -1,233 +1,310 @@
-use rayon::prelude::*;
+use crate::parse::{self, ParseError};
+use crate::solution::{Answer, Solution};
+use rand::{Rng, RngExt};
 
 type Input = Vec<(String, Vec<usize>)>;
 
 #[aoc_generator(day12)]
-pub fn load_input(input: &str) -> Input {
+pub fn load_input(input: &str) -> Result<Input, ParseError> {
     let mut output = vec![];
-    for line in input.lines() {
-        let mut part_iter = line.split(' ');
-        let arrangement = part_iter.next().unwrap().to_string();
-        let right = part_iter.next().unwrap();
-        let segments: Vec<usize> = right
-            .split(',')
-            .map(|s| s.parse::<usize>().unwrap())
-            .collect();
+    for line in parse::lines(12, input) {
+        let (arrangement, right) = line.split_once(line.text, " ")?;
+        let arrangement = line.only(arrangement, "?.#")?.to_string();
+        let segments: Vec<usize> = line.list(right, ',')?;
         output.push((arrangement, segments));
     }
-    output
+    Ok(output)
 }
 
-// A "chunk" is some number of '#'s with a '.' on both sides.
-fn build_chunk(n: usize) -> String {
-    let mut output = String::from(".");
-    for _ in 0..n {
-        output.push('#');
+// Where reading `c` takes the state of having finished `g` groups and being `run` #s into the
+// next one, if anywhere.
+fn step(groups: &[usize], (g, run): (usize, usize), c: u8) -> Option<(usize, usize)> {
+    match c {
+        b'#' if g < groups.len() && run < groups[g] => Some((g, run + 1)),
+        b'.' if run == 0 => Some((g, 0)),
+        b'.' if g < groups.len() && run == groups[g] => Some((g + 1, 0)),
+        _ => None,
     }
-    output.push('.');
-    output
 }
 
-// Checks if every char in `arr` beginning at the nth is either `?` or matches the corresponding
-// char in `seq`.
-// NOTE: This is called like a million-billion times, any speedups here will payoff greatly.
-fn check_seq(n: usize, seq: &str, arr: &str) -> bool {
-    arr.chars()
-        .skip(n)
-        .take(seq.len())
-        .zip(seq.chars())
-        .all(|(c1, c2)| c1 == '?' || c1 == c2)
+// Either every group is done, or the last one runs right up to the end
+fn accepts(groups: &[usize], (g, run): (usize, usize)) -> bool {
+    (g, run) == (groups.len(), 0) || g + 1 == groups.len() && run == groups[g]
 }
 
-fn check_remaining_hashes(n: usize, seq: &str, arr: &str) -> bool {
-    // Don't skip any '#'s
-    if let Some(ridx) = arr.rfind('#') {
-        let last_hash_idx = n + seq.len() - 2;
-        if last_hash_idx < ridx {
-            return false;
-        }
+// The choices for one spring, in lexicographic order
+fn choices(c: u8) -> &'static [u8] {
+    match c {
+        b'?' => b"#.",
+        b'#' => b"#",
+        _ => b".",
     }
-    true
 }
 
-// The concept here is that a chunk of #'s will never be a valid match on leading '.', only on '?'
-// or '#', so just skip to those.
-fn start_lower_bound(arr: &str) -> usize {
-    if let Some(idx) = arr.find(['?', '#']) {
-        if idx > 0 {
-            return idx - 1;
-        } else {
-            return 0;
+/// How many ways the `?`s in `springs` can be filled in so the runs of `#` are exactly `groups`.
+///
+/// Walks the springs once, keeping a count for every (group index, length of the current run)
+/// pair, so this is polynomial rather than trying every placement.
+pub fn count_arrangements(springs: &[u8], groups: &[usize]) -> u128 {
+    let width = groups.iter().copied().max().unwrap_or(0) + 1;
+    // counts[g * width + run]: `g` groups finished and `run` #s into the next one
+    let mut counts = vec![0u128; (groups.len() + 1) * width];
+    counts[0] = 1;
+    for &c in springs {
+        let mut next = vec![0u128; counts.len()];
+        for (i, &n) in counts.iter().enumerate().filter(|(_, &n)| n > 0) {
+            for &choice in choices(c) {
+                if let Some((g, run)) = step(groups, (i / width, i % width), choice) {
+                    next[g * width + run] += n;
+                }
+            }
         }
+        counts = next;
     }
-    arr.len() - 1
+    (0..counts.len())
+        .filter(|&i| accepts(groups, (i / width, i % width)))
+        .map(|i| counts[i])
+        .sum()
 }
 
-// This function is calculating an upper bound on the number of spaces we actually have to scan by
-// looking at the length of the totall array less the length of the sum of the chunks.
-fn enumerate_idxs(chunks: &[String], arr: &str) -> usize {
-    let minchunklen = chunks.iter().map(|c| c.len()).sum::<usize>() - (chunks.len() - 1);
-    let mut output = arr.len() - minchunklen + 1;
-    if let Some(first_hash) = arr.find('#') {
-        // Can't skip any explicit '#'s, so this is an upper bound when present.
-        if first_hash < output {
-            output = first_hash;
+/// Every concrete way to fill in one row, in lexicographic order (`#` before `.`).
+///
+/// Counts the ways to finish the row from each position and state up front, which is enough to
+/// walk straight to the k-th arrangement without generating the ones before it.
+#[derive(Clone, Debug)]
+pub struct Arrangements {
+    springs: Vec<u8>,
+    groups: Vec<usize>,
+    width: usize,
+    // completions[i * states + g * width + run]: ways to fill in `springs[i..]` from that state
+    completions: Vec<u128>,
+}
+
+impl Arrangements {
+    pub fn new(springs: &str, groups: &[usize]) -> Self {
+        let springs = springs.as_bytes().to_vec();
+        let width = groups.iter().copied().max().unwrap_or(0) + 1;
+        let states = (groups.len() + 1) * width;
+        let mut completions = vec![0u128; (springs.len() + 1) * states];
+        for s in 0..states {
+            if accepts(groups, (s / width, s % width)) {
+                completions[springs.len() * states + s] = 1;
+            }
+        }
+        for i in (0..springs.len()).rev() {
+            for s in 0..states {
+                completions[i * states + s] = choices(springs[i])
+                    .iter()
+                    .filter_map(|&c| step(groups, (s / width, s % width), c))
+                    .map(|(g, run)| completions[(i + 1) * states + g * width + run])
+                    .sum();
+            }
+        }
+        Self {
+            springs,
+            groups: groups.to_vec(),
+            width,
+            completions,
         }
     }
-    output
-}
 
-fn solve(chunks: &[String], arr: &str) -> usize {
-    // Get a lower bound on the starting index.
-    let i_lower = start_lower_bound(arr);
-
-    // This grabs an upper bound of the number of spaces we need to check.
-    let space = enumerate_idxs(chunks, arr);
-
-    (i_lower..space)
-        .into_par_iter()
-        .map(|i| {
-            if chunks.len() == 1 {
-                // This is the last chunk, is _must_ catch the '#' detected by `i_lower` so we only have to
-                // do 1 check here else it's no good. Also it must cover the _remaining_ '#'s as well.
-                if check_seq(i, &chunks[0], arr) && check_remaining_hashes(i, &chunks[0], arr) {
-                    1
-                } else {
-                    0
-                }
-            } else {
-                // More than 1 chunk means sum up all valid combos for each chunk index
-                if check_seq(i, &chunks[0], arr) {
-                    // If we get here then _this_ chunk is valid, so what about chunks farther on?
-                    let offset = i + chunks[0].len() - 1;
-                    solve(&chunks[1..], &arr[offset..])
-                } else {
-                    0
+    fn states(&self) -> usize {
+        (self.groups.len() + 1) * self.width
+    }
+
+    pub fn count(&self) -> u128 {
+        self.completions[0]
+    }
+
+    /// The `k`-th arrangement counting from 0, or `None` if there aren't that many.
+    pub fn kth(&self, mut k: u128) -> Option<String> {
+        if k >= self.count() {
+            return None;
+        }
+        let states = self.states();
+        let mut state = (0, 0);
+        let mut output = String::with_capacity(self.springs.len());
+        for (i, &c) in self.springs.iter().enumerate() {
+            for &choice in choices(c) {
+                let Some(next) = step(&self.groups, state, choice) else {
+                    continue;
+                };
+                let n = self.completions[(i + 1) * states + next.0 * self.width + next.1];
+                if k < n {
+                    output.push(choice as char);
+                    state = next;
+                    break;
                 }
+                // Skip everything that starts with this choice
+                k -= n;
             }
-        })
-        .sum()
+        }
+        Some(output)
+    }
+
+    /// One arrangement, each as likely as any other.
+    pub fn random(&self, rng: &mut impl Rng) -> Option<String> {
+        if self.count() == 0 {
+            return None;
+        }
+        self.kth(rng.random_range(0..self.count()))
+    }
+
+    /// Every arrangement in order, built as they're asked for.
+    pub fn iter(&self) -> impl Iterator<Item = String> + '_ {
+        (0..self.count()).map(|k| self.kth(k).unwrap())
+    }
 }
 
-#[aoc(day12, part1)]
-pub fn part1(input: &Input) -> usize {
+/// `factor` copies of the springs joined by `?`, and of the groups.
+pub fn unfold(springs: &str, groups: &[usize], factor: usize) -> (String, Vec<usize>) {
+    let springs = vec![springs; factor].join("?");
+    (springs, groups.repeat(factor))
+}
+
+/// The sum of the arrangement counts over every row unfolded `factor` times.
+pub fn total_arrangements(input: &Input, factor: usize) -> u128 {
     input
         .iter()
-        .map(|(arr, seg)| {
-            let chunks: Vec<_> = seg.iter().map(|n| build_chunk(*n)).collect();
-            let mut aug_arr = arr.clone();
-            aug_arr.insert(0, '.');
-            aug_arr.push('.');
-            solve(&chunks, &aug_arr)
+        .map(|(springs, groups)| {
+            let (springs, groups) = unfold(springs, groups, factor);
+            count_arrangements(springs.as_bytes(), &groups)
         })
         .sum()
 }
 
-fn unfold(arr: &str) -> String {
-    let mut output = String::new();
-    for i in 0..5 {
-        output.push_str(arr);
-        if i < 4 {
-            output.push('?');
-        }
-    }
-    output
+#[aoc(day12, part1)]
+pub fn part1(input: &Input) -> u128 {
+    total_arrangements(input, 1)
 }
 
-#[allow(dead_code)]
-fn solve2(chunks: &[String], arr: &str) -> usize {
-    let first = solve(chunks, arr);
+#[aoc(day12, part2)]
+pub fn part2(input: &Input) -> u128 {
+    total_arrangements(input, 5)
+}
 
-    let minchunklen = chunks.iter().map(|c| c.len()).sum::<usize>() - (chunks.len() - 1);
-    if (arr.chars().nth(1).unwrap() == '#' || arr.chars().nth(arr.len() - 2).unwrap() == '#')
-        && minchunklen == arr.len()
-    {
-        return first.pow(5);
-    }
+pub struct Day12;
 
-    let mut alt0 = arr.to_string();
-    alt0.insert(arr.len() - 2, '?');
+impl Solution for Day12 {
+    type Input = Input;
 
-    let mut alt1 = arr.to_string();
-    alt1.insert(1, '?');
+    const DAY: u32 = 12;
 
-    let alt0_cnt = solve(chunks, &alt0);
-    let alt1_cnt = solve(chunks, &alt1);
-    if alt0_cnt > alt1_cnt {
-        first * alt0_cnt.pow(4)
-    } else {
-        first * alt1_cnt.pow(4)
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        load_input(input)
     }
-}
 
-#[aoc(day12, part2)]
-// WARNING: > 12 hour runtime.
-pub fn part2(input: &Input) -> usize {
-    input
-        .par_iter()
-        .map(|(arr, _seg)| {
-            let arr = unfold(arr);
-            let mut seg = vec![];
-            for _ in 0..5 {
-                let mut temp = _seg.clone();
-                seg.append(&mut temp);
-            }
-            let chunks: Vec<_> = seg.iter().map(|n| build_chunk(*n)).collect();
-            let mut aug_arr = arr.clone();
-            aug_arr.insert(0, '.');
-            aug_arr.push('.');
-            solve(&chunks, &aug_arr)
-        })
-        .sum()
-    /*
-    input
-        .iter()
-        .map(|(arr, seg)| {
-            let chunks: Vec<_> = seg.iter().map(|n| build_chunk(*n)).collect();
-            let mut aug_arr = arr.clone();
-            aug_arr.insert(0, '.');
-            aug_arr.push('.');
-            let value = solve2(&chunks, &aug_arr);
-            value
-        })
-        .sum()
-    */
+    fn part1(input: &Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Input) -> Answer {
+        part2(input).into()
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::collections::HashSet;
     use std::fs::read_to_string;
 
     #[test]
     fn test_first() {
-        let input = load_input("???.### 1,1,3");
+        let input = load_input("???.### 1,1,3").unwrap();
         assert_eq!(part1(&input), 1);
     }
 
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2023/12a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 21);
 
-        let input = load_input(".?##?.??#.. 2,2");
+        let input = load_input(".?##?.??#.. 2,2").unwrap();
         assert_eq!(part1(&input), 1);
 
-        let input = load_input("..??#?????.#??. 3,1");
+        let input = load_input("..??#?????.#??. 3,1").unwrap();
         assert_eq!(part1(&input), 3);
     }
 
     #[test]
     fn test_second() {
-        let input = load_input("???.### 1,1,3");
+        let input = load_input("???.### 1,1,3").unwrap();
         assert_eq!(part2(&input), 1);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2023/12a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 525152);
     }
+
+    #[test]
+    fn test_unfold() {
+        let (springs, groups) = unfold(".??..??...?##.", &[1, 1, 3], 5);
+        assert_eq!(count_arrangements(springs.as_bytes(), &groups), 16384);
+        assert_eq!(count_arrangements(b"?###????????", &[3, 2, 1]), 10);
+        assert_eq!(count_arrangements(b"??", &[]), 1);
+        assert_eq!(count_arrangements(b"#", &[]), 0);
+
+        // Big enough to overflow a u64
+        let (springs, groups) = unfold("?", &[], 200);
+        assert_eq!(count_arrangements(springs.as_bytes(), &groups), 1);
+        let (springs, groups) = unfold("??", &[1], 40);
+        assert!(count_arrangements(springs.as_bytes(), &groups) > u64::MAX as u128);
+    }
+
+    // The lengths of the runs of #s
+    fn runs(s: &str) -> Vec<usize> {
+        s.split('.')
+            .filter(|r| !r.is_empty())
+            .map(str::len)
+            .collect()
+    }
+
+    #[test]
+    fn test_arrangements() {
+        let input = read_to_string("input/2023/12a.txt").unwrap();
+        for (springs, groups) in load_input(&input).unwrap() {
+            let arrangements = Arrangements::new(&springs, &groups);
+            let all: Vec<String> = arrangements.iter().collect();
+            assert_eq!(all.len() as u128, arrangements.count());
+            assert_eq!(
+                arrangements.count(),
+                count_arrangements(springs.as_bytes(), &groups)
+            );
+            // Strictly increasing, so sorted and without repeats
+            assert!(all.windows(2).all(|w| w[0] < w[1]));
+            for a in &all {
+                assert_eq!(runs(a), groups);
+                let fits = a.bytes().zip(springs.bytes());
+                assert!(fits.into_iter().all(|(x, y)| y == b'?' || x == y));
+            }
+        }
+
+        let arrangements = Arrangements::new("?###????????", &[3, 2, 1]);
+        assert_eq!(arrangements.kth(0).unwrap(), ".###.##.#...");
+        assert_eq!(arrangements.kth(9).unwrap(), ".###....##.#");
+        assert_eq!(arrangements.kth(10), None);
+
+        // Unfolded rows are far too many to list, but can still be picked from
+        let (springs, groups) = unfold("?###????????", &[3, 2, 1], 5);
+        let arrangements = Arrangements::new(&springs, &groups);
+        assert_eq!(arrangements.count(), 506250);
+        let last = arrangements.kth(arrangements.count() - 1).unwrap();
+        assert_eq!(runs(&last), groups);
+
+        let mut rng = StdRng::seed_from_u64(12);
+        let arrangements = Arrangements::new("??.??", &[1]);
+        let mut seen = HashSet::new();
+        for _ in 0..100 {
+            seen.insert(arrangements.random(&mut rng).unwrap());
+        }
+        assert_eq!(seen.len(), 4);
+        assert_eq!(Arrangements::new("#", &[2]).random(&mut rng), None);
+    }
 }
@@ -1,37 +1,27 @@
-use std::collections::HashMap;
+use crate::grid::Grid;
+use crate::parse::{self, ParseError};
+use crate::solution::{Answer, Solution};
+use tracing::warn;
 
-type Input = Vec<HashMap<(i64, i64), char>>;
+type Input = Vec<Grid<char>>;
 
 #[aoc_generator(day13)]
-pub fn load_input(input: &str) -> Input {
-    let mut output = vec![];
-    let mut map = HashMap::new();
-    let mut y = 0;
-    for line in input.lines() {
-        if line.is_empty() {
-            output.push(map);
-            map = HashMap::new();
-            y = 0;
-        } else {
-            for (x, c) in line.chars().enumerate() {
-                map.insert((x as i64, y as i64), c);
-            }
-            y += 1;
-        }
-    }
-    output.push(map);
-    output
+pub fn load_input(input: &str) -> Result<Input, ParseError> {
+    parse::blocks(13, input)
+        .into_iter()
+        .map(|pattern| Grid::parse(pattern, "#."))
+        .collect()
 }
 
-fn find_reflect_x(map: &HashMap<(i64, i64), char>) -> Option<i64> {
-    let xmax = *map.keys().map(|(x, _)| x).max().unwrap();
-    let ymax = *map.keys().map(|(_, y)| y).max().unwrap();
+fn find_reflect_x(map: &Grid<char>) -> Option<i64> {
+    let xmax = map.width() as i64 - 1;
+    let ymax = map.height() as i64 - 1;
     let mut candidates = vec![];
     for x in 0..xmax {
         let mut bail = false;
         for y in 0..ymax + 1 {
-            let c1 = map.get(&(x, y));
-            let c2 = map.get(&(x + 1, y));
+            let c1 = map.get((x, y));
+            let c2 = map.get((x + 1, y));
             if c1 != c2 {
                 bail = true;
                 break;
@@ -57,8 +47,8 @@ fn find_reflect_x(map: &HashMap<(i64, i64), char>) -> Option<i64> {
             let x1 = cand - i;
             let x2 = cand + i + 1;
             for y in 0..ymax + 1 {
-                let c1 = map.get(&(x1, y));
-                let c2 = map.get(&(x2, y));
+                let c1 = map.get((x1, y));
+                let c2 = map.get((x2, y));
                 if c1 != c2 {
                     bail = true;
                     break;
@@ -75,15 +65,15 @@ fn find_reflect_x(map: &HashMap<(i64, i64), char>) -> Option<i64> {
     None
 }
 
-fn find_reflect_y(map: &HashMap<(i64, i64), char>) -> Option<i64> {
-    let xmax = *map.keys().map(|(x, _)| x).max().unwrap();
-    let ymax = *map.keys().map(|(_, y)| y).max().unwrap();
+fn find_reflect_y(map: &Grid<char>) -> Option<i64> {
+    let xmax = map.width() as i64 - 1;
+    let ymax = map.height() as i64 - 1;
     let mut candidates = vec![];
     for y in 0..ymax {
         let mut bail = false;
         for x in 0..xmax + 1 {
-            let c1 = map.get(&(x, y));
-            let c2 = map.get(&(x, y + 1));
+            let c1 = map.get((x, y));
+            let c2 = map.get((x, y + 1));
             if c1 != c2 {
                 bail = true;
                 break;
@@ -109,8 +99,8 @@ fn find_reflect_y(map: &HashMap<(i64, i64), char>) -> Option<i64> {
             let y1 = cand - i;
             let y2 = cand + i + 1;
             for x in 0..xmax + 1 {
-                let c1 = map.get(&(x, y1));
-                let c2 = map.get(&(x, y2));
+                let c1 = map.get((x, y1));
+                let c2 = map.get((x, y2));
                 if c1 != c2 {
                     //println!("y1: {}, y2: {}", y1, y2);
                     bail = true;
@@ -145,16 +135,15 @@ pub fn part1(input: &Input) -> i64 {
         }
 
         if !done {
-            println!();
-            print_map(map);
+            warn!("no reflection found in\n{}", map);
         }
     }
     output
 }
 
-fn calc_metric_x(x: i64, map: &HashMap<(i64, i64), char>) -> i64 {
-    let xmax = *map.keys().map(|(x, _)| x).max().unwrap();
-    let ymax = *map.keys().map(|(_, y)| y).max().unwrap();
+fn calc_metric_x(x: i64, map: &Grid<char>) -> i64 {
+    let xmax = map.width() as i64 - 1;
+    let ymax = map.height() as i64 - 1;
     let mut metric = 0;
 
     let mut steps = x + 1;
@@ -164,8 +153,8 @@ fn calc_metric_x(x: i64, map: &HashMap<(i64, i64), char>) -> i64 {
 
     for i in 0..steps {
         for y in 0..ymax + 1 {
-            let c1 = map.get(&(x - i, y));
-            let c2 = map.get(&(x + i + 1, y));
+            let c1 = map.get((x - i, y));
+            let c2 = map.get((x + i + 1, y));
             if c1 != c2 {
                 metric += 1;
             }
@@ -174,9 +163,9 @@ fn calc_metric_x(x: i64, map: &HashMap<(i64, i64), char>) -> i64 {
     metric
 }
 
-fn calc_metric_y(y: i64, map: &HashMap<(i64, i64), char>) -> i64 {
-    let xmax = *map.keys().map(|(x, _)| x).max().unwrap();
-    let ymax = *map.keys().map(|(_, y)| y).max().unwrap();
+fn calc_metric_y(y: i64, map: &Grid<char>) -> i64 {
+    let xmax = map.width() as i64 - 1;
+    let ymax = map.height() as i64 - 1;
     let mut metric = 0;
 
     let mut steps = y + 1;
@@ -186,8 +175,8 @@ fn calc_metric_y(y: i64, map: &HashMap<(i64, i64), char>) -> i64 {
 
     for i in 0..steps {
         for x in 0..xmax + 1 {
-            let c1 = map.get(&(x, y - i));
-            let c2 = map.get(&(x, y + i + 1));
+            let c1 = map.get((x, y - i));
+            let c2 = map.get((x, y + i + 1));
             if c1 != c2 {
                 metric += 1;
             }
@@ -196,13 +185,13 @@ fn calc_metric_y(y: i64, map: &HashMap<(i64, i64), char>) -> i64 {
     metric
 }
 
-fn x_metrics(map: &HashMap<(i64, i64), char>) -> Vec<i64> {
-    let xmax = *map.keys().map(|(x, _)| x).max().unwrap();
+fn x_metrics(map: &Grid<char>) -> Vec<i64> {
+    let xmax = map.width() as i64 - 1;
     (0..xmax).map(|x| calc_metric_x(x, map)).collect()
 }
 
-fn y_metrics(map: &HashMap<(i64, i64), char>) -> Vec<i64> {
-    let ymax = *map.keys().map(|(_, y)| y).max().unwrap();
+fn y_metrics(map: &Grid<char>) -> Vec<i64> {
+    let ymax = map.height() as i64 - 1;
     (0..ymax).map(|y| calc_metric_y(y, map)).collect()
 }
 
@@ -233,23 +222,29 @@ pub fn part2(input: &Input) -> i64 {
         }
 
         if !done {
-            println!();
-            print_map(map);
+            warn!("no reflection found in\n{}", map);
         }
     }
     output
 }
 
-#[allow(dead_code)]
-fn print_map(map: &HashMap<(i64, i64), char>) {
-    let xmax = *map.keys().map(|(x, _)| x).max().unwrap();
-    let ymax = *map.keys().map(|(_, y)| y).max().unwrap();
-    for y in 0..ymax + 1 {
-        for x in 0..xmax + 1 {
-            let c = map.get(&(x, y)).unwrap();
-            print!("{}", c);
-        }
-        println!();
+pub struct Day13;
+
+impl Solution for Day13 {
+    type Input = Input;
+
+    const DAY: u32 = 13;
+
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        load_input(input)
+    }
+
+    fn part1(input: &Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Input) -> Answer {
+        part2(input).into()
     }
 }
 
@@ -261,14 +256,14 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2023/13a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 405);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2023/13a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 400);
     }
 }
@@ -1,21 +1,21 @@
-use std::collections::HashMap;
+use crate::grid::Grid;
+use crate::parse::ParseError;
+use crate::solution::{Answer, Solution};
+use crate::viewer::{Frame, Palette, Rgb};
+use std::collections::{HashMap, HashSet};
+use tracing::debug;
 
-type Input = Vec<Vec<char>>;
+type Input = Grid<char>;
 
 #[aoc_generator(day14)]
-pub fn load_input(input: &str) -> Input {
-    let mut output = vec![];
-    for line in input.lines() {
-        let row = line.chars().collect();
-        output.push(row);
-    }
-    output
+pub fn load_input(input: &str) -> Result<Input, ParseError> {
+    Grid::parse_str(14, input, "O#.")
 }
 
-fn tilt_north(map: &[Vec<char>]) -> Input {
-    let nrows = map.len();
-    let ncols = map[0].len();
-    let mut output = vec![vec!['.'; ncols]; nrows];
+fn tilt_north(map: &Grid<char>) -> Input {
+    let nrows = map.height();
+    let ncols = map.width();
+    let mut output = Grid::new(ncols, nrows, '.');
 
     // Walk down columns, gather 'O' to top.
     // Reset the "top" every '#'.
@@ -23,14 +23,14 @@ fn tilt_north(map: &[Vec<char>]) -> Input {
         let mut top = 0;
         let mut nstones = 0;
         for y in 0..nrows {
-            match map[y][x] {
+            match map[(x, y)] {
                 '.' => (),
                 'O' => nstones += 1,
                 '#' => {
-                    output[y][x] = '#';
+                    output[(x, y)] = '#';
                     // Fill in stones here
                     for i in 0..nstones {
-                        output[top + i][x] = 'O';
+                        output[(x, top + i)] = 'O';
                     }
                     top = y + 1;
                     nstones = 0;
@@ -42,17 +42,17 @@ fn tilt_north(map: &[Vec<char>]) -> Input {
         // Push up stones one last time before next column
         // Fill in stones here
         for i in 0..nstones {
-            output[top + i][x] = 'O';
+            output[(x, top + i)] = 'O';
         }
     }
 
     output
 }
 
-fn count_score(map: &[Vec<char>]) -> usize {
+fn count_score(map: &Grid<char>) -> usize {
     let mut output = 0;
-    let nrows = map.len();
-    for (i, row) in map.iter().enumerate() {
+    let nrows = map.height();
+    for (i, row) in map.rows().enumerate() {
         let nrocks = row.iter().filter(|c| **c == 'O').count();
         let value = nrows - i;
         output += value * nrocks;
@@ -66,10 +66,10 @@ pub fn part1(input: &Input) -> usize {
     count_score(&tilted)
 }
 
-fn tilt_west(map: &[Vec<char>]) -> Input {
-    let nrows = map.len();
-    let ncols = map[0].len();
-    let mut output = vec![vec!['.'; ncols]; nrows];
+fn tilt_west(map: &Grid<char>) -> Input {
+    let nrows = map.height();
+    let ncols = map.width();
+    let mut output = Grid::new(ncols, nrows, '.');
 
     // Walk across rows (left to right), gather 'O' to left/west.
     // Reset the "wall" every '#'.
@@ -77,14 +77,14 @@ fn tilt_west(map: &[Vec<char>]) -> Input {
         let mut wall = 0;
         let mut nstones = 0;
         for x in 0..ncols {
-            match map[y][x] {
+            match map[(x, y)] {
                 '.' => (),
                 'O' => nstones += 1,
                 '#' => {
-                    output[y][x] = '#';
+                    output[(x, y)] = '#';
                     // Fill in stones here
                     for i in 0..nstones {
-                        output[y][wall + i] = 'O';
+                        output[(wall + i, y)] = 'O';
                     }
                     wall = x + 1;
                     nstones = 0;
@@ -96,17 +96,17 @@ fn tilt_west(map: &[Vec<char>]) -> Input {
         // Push stones one last time before next row
         // Fill in stones here
         for i in 0..nstones {
-            output[y][wall + i] = 'O';
+            output[(wall + i, y)] = 'O';
         }
     }
 
     output
 }
 
-fn tilt_south(map: &[Vec<char>]) -> Input {
-    let nrows = map.len();
-    let ncols = map[0].len();
-    let mut output = vec![vec!['.'; ncols]; nrows];
+fn tilt_south(map: &Grid<char>) -> Input {
+    let nrows = map.height();
+    let ncols = map.width();
+    let mut output = Grid::new(ncols, nrows, '.');
 
     // Walk down columns, gather 'O' to top.
     // Reset the "top" every '#'.
@@ -114,14 +114,14 @@ fn tilt_south(map: &[Vec<char>]) -> Input {
         let mut top = nrows - 1;
         let mut nstones = 0;
         for y in (0..nrows).rev() {
-            match map[y][x] {
+            match map[(x, y)] {
                 '.' => (),
                 'O' => nstones += 1,
                 '#' => {
-                    output[y][x] = '#';
+                    output[(x, y)] = '#';
                     // Fill in stones here
                     for i in 0..nstones {
-                        output[top - i][x] = 'O';
+                        output[(x, top - i)] = 'O';
                     }
                     if y > 0 {
                         top = y - 1;
@@ -137,17 +137,17 @@ fn tilt_south(map: &[Vec<char>]) -> Input {
         // Push up stones one last time before next column
         // Fill in stones here
         for i in 0..nstones {
-            output[top - i][x] = 'O';
+            output[(x, top - i)] = 'O';
         }
     }
 
     output
 }
 
-fn tilt_east(map: &[Vec<char>]) -> Input {
-    let nrows = map.len();
-    let ncols = map[0].len();
-    let mut output = vec![vec!['.'; ncols]; nrows];
+fn tilt_east(map: &Grid<char>) -> Input {
+    let nrows = map.height();
+    let ncols = map.width();
+    let mut output = Grid::new(ncols, nrows, '.');
 
     // Walk across rows (left to right), gather 'O' to left/west.
     // Reset the "wall" every '#'.
@@ -155,14 +155,14 @@ fn tilt_east(map: &[Vec<char>]) -> Input {
         let mut wall = ncols - 1;
         let mut nstones = 0;
         for x in (0..ncols).rev() {
-            match map[y][x] {
+            match map[(x, y)] {
                 '.' => (),
                 'O' => nstones += 1,
                 '#' => {
-                    output[y][x] = '#';
+                    output[(x, y)] = '#';
                     // Fill in stones here
                     for i in 0..nstones {
-                        output[y][wall - i] = 'O';
+                        output[(wall - i, y)] = 'O';
                     }
                     if x > 0 {
                         wall = x - 1;
@@ -178,14 +178,14 @@ fn tilt_east(map: &[Vec<char>]) -> Input {
         // Push stones one last time before next row
         // Fill in stones here
         for i in 0..nstones {
-            output[y][wall - i] = 'O';
+            output[(wall - i, y)] = 'O';
         }
     }
 
     output
 }
 
-fn do_cycle(map: &[Vec<char>]) -> Input {
+fn do_cycle(map: &Grid<char>) -> Input {
     // Cycle is north, west, south, east
     let map = tilt_north(map);
     let map = tilt_west(&map);
@@ -193,41 +193,82 @@ fn do_cycle(map: &[Vec<char>]) -> Input {
     tilt_east(&map)
 }
 
+/// Every tilt of the spin cycle, until the platform is back in a state it's been in before.
+pub fn tilt_frames(input: &Input) -> Vec<Frame> {
+    type Tilt = fn(&Grid<char>) -> Input;
+    let tilts: [(&str, Tilt); 4] = [
+        ("north", tilt_north),
+        ("west", tilt_west),
+        ("south", tilt_south),
+        ("east", tilt_east),
+    ];
+    let mut frames = vec![Frame::new(input.clone()).with_caption("start")];
+    let mut seen = HashSet::new();
+    let mut map = input.clone();
+    for cycle in 1.. {
+        for (name, tilt) in tilts {
+            map = tilt(&map);
+            let caption = format!("cycle {} {}: load {}", cycle, name, count_score(&map));
+            frames.push(Frame::new(map.clone()).with_caption(caption));
+        }
+        if !seen.insert(map.clone()) {
+            break;
+        }
+    }
+    frames
+}
+
 #[aoc(day14, part2)]
 pub fn part2(input: &Input) -> usize {
     let mut map = input.clone();
     let mut statemap: HashMap<Input, usize> = HashMap::new();
+    // The state after each cycle, `history[t - 1]` is the state after `t`
+    let mut history = vec![];
     let mut t: usize = 1;
     loop {
         map = do_cycle(&map);
-        //print_map(&map);
+        //println!("{}", map);
         if let Some(last_t) = statemap.insert(map.clone(), t) {
-            println!(
-                "Hit state cycle at t: {}, last encountered at t: {}",
-                t, last_t
-            );
+            debug!(t, last_t, "hit state cycle");
             let cycle_length = t - last_t;
             let idx = (1_000_000_000 - last_t) % cycle_length;
-            let final_state = statemap
-                .iter()
-                .find(|(_k, v)| **v == idx + last_t)
-                .map(|(k, _v)| k)
-                .unwrap();
-            return count_score(final_state);
+            return count_score(&history[idx + last_t - 1]);
         }
+        history.push(map.clone());
         t += 1;
     }
 }
 
-#[allow(dead_code)]
-fn print_map(map: &[Vec<char>]) {
-    for row in map {
-        for c in row {
-            print!("{}", c);
-        }
-        println!();
+pub struct Day14;
+
+impl Solution for Day14 {
+    type Input = Input;
+
+    const DAY: u32 = 14;
+
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        load_input(input)
+    }
+
+    fn part1(input: &Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Input) -> Answer {
+        part2(input).into()
+    }
+
+    fn frames(input: &Input) -> Vec<Frame> {
+        tilt_frames(input)
+    }
+
+    fn palette() -> Palette {
+        Palette::new(&[
+            ('O', Rgb(230, 180, 60)),
+            ('#', Rgb(110, 110, 140)),
+            ('.', Rgb(60, 60, 60)),
+        ])
     }
-    println!();
 }
 
 #[cfg(test)]
@@ -238,14 +279,25 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2023/14a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 136);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2023/14a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 64);
+        // Settles straight away, so the loop is a single cycle long
+        assert_eq!(part2(&load_input("O.\n.#").unwrap()), 1);
+        assert_eq!(part2(&load_input("").unwrap()), 0);
+
+        // The example settles into a loop of 7 cycles after the third
+        let frames = tilt_frames(&input);
+        assert_eq!(frames.len(), 1 + 4 * 10);
+        assert_eq!(
+            frames.last().unwrap().caption.split(':').next(),
+            Some("cycle 10 east")
+        );
     }
 }
@@ -1,8 +1,50 @@
-type Input = String;
+use crate::parse::{self, ParseError};
+use crate::solution::{Answer, Solution};
+type Input = Vec<Step>;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Operation {
+    Remove,
+    Insert(usize),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Step {
+    // The whole step as written, which is what part 1 hashes
+    text: String,
+    label: String,
+    operation: Operation,
+}
 
 #[aoc_generator(day15)]
-pub fn load_input(input: &str) -> Input {
-    String::from(input)
+pub fn load_input(input: &str) -> Result<Input, ParseError> {
+    let mut steps = vec![];
+    // The steps are normally all on one line, blank lines are skipped
+    for line in parse::lines(15, input).filter(|l| !l.text.is_empty()) {
+        for token in line.text.split(',') {
+            let Some(at) = token.find(['=', '-']) else {
+                return Err(line.error_after(token, "`=` or `-`"));
+            };
+            let (label, rest) = token.split_at(at);
+            if label.is_empty() {
+                return Err(line.error(label, "a label"));
+            }
+            let operation = match rest.split_at(1) {
+                ("-", "") => Operation::Remove,
+                ("-", extra) => return Err(line.error(extra, "`,` after `-`")),
+                (_, value) => Operation::Insert(line.parse::<usize>(value, "a focal length")?),
+            };
+            steps.push(Step {
+                text: token.to_string(),
+                label: label.to_string(),
+                operation,
+            });
+        }
+    }
+    if steps.is_empty() {
+        return Err(ParseError::new(15, 1, 1, "a step", ""));
+    }
+    Ok(steps)
 }
 
 fn hash(token: &str) -> usize {
@@ -17,52 +59,39 @@ fn hash(token: &str) -> usize {
 
 #[aoc(day15, part1)]
 pub fn part1(input: &Input) -> usize {
-    input.trim_end().split(',').map(hash).sum()
+    input.iter().map(|step| hash(&step.text)).sum()
 }
 
 #[aoc(day15, part2)]
 pub fn part2(input: &Input) -> usize {
     let mut boxes: Vec<Vec<(String, usize)>> = vec![vec![]; 256];
-    let tokens: Vec<_> = input.trim_end().split(',').collect();
-
-    for t in tokens {
-        if t.ends_with(&['-']) {
-            // Remove lens
-            let sides: Vec<_> = t.split('-').collect();
-            let label = sides[0].to_string();
-            let boxidx = hash(&label);
-            let mut replace_idx = None;
-            for (i, (l, _)) in boxes[boxidx].iter().enumerate() {
-                if l == &label {
-                    replace_idx = Some(i);
-                }
-            }
-            if let Some(ridx) = replace_idx {
-                boxes[boxidx].remove(ridx);
+
+    for step in input {
+        let label = &step.label;
+        let boxidx = hash(label);
+        let mut replace_idx = None;
+        for (i, (l, _)) in boxes[boxidx].iter().enumerate() {
+            if l == label {
+                replace_idx = Some(i);
             }
-        } else {
-            // Place/replace lens
-            let sides: Vec<_> = t.split('=').collect();
-            let label = sides[0].to_string();
-            let boxidx = hash(&label);
-            let value = sides[1].parse::<usize>().unwrap();
-            let contents = (label.clone(), value);
-
-            let mut replace_idx = None;
-            for (i, (l, _)) in boxes[boxidx].iter().enumerate() {
-                if l == &label {
-                    replace_idx = Some(i);
+        }
+        match step.operation {
+            Operation::Remove => {
+                // Remove lens
+                if let Some(ridx) = replace_idx {
+                    boxes[boxidx].remove(ridx);
                 }
             }
-
-            if let Some(ridx) = replace_idx {
-                // Replace
-                if let Some(cont) = boxes[boxidx].get_mut(ridx) {
-                    *cont = contents;
+            Operation::Insert(value) => {
+                // Place/replace lens
+                let contents = (label.clone(), value);
+                if let Some(ridx) = replace_idx {
+                    // Replace
+                    boxes[boxidx][ridx] = contents;
+                } else {
+                    // Emplace
+                    boxes[boxidx].push(contents);
                 }
-            } else {
-                // Emplace
-                boxes[boxidx].push(contents);
             }
         }
     }
@@ -77,6 +106,26 @@ pub fn part2(input: &Input) -> usize {
     lenses.iter().sum()
 }
 
+pub struct Day15;
+
+impl Solution for Day15 {
+    type Input = Input;
+
+    const DAY: u32 = 15;
+
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        load_input(input)
+    }
+
+    fn part1(input: &Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Input) -> Answer {
+        part2(input).into()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -85,14 +134,27 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2023/15a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 1320);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2023/15a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
+        assert_eq!(part2(&input), 145);
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = load_input("rn=x").unwrap_err();
+        assert_eq!((err.column, err.expected.as_str()), (4, "a focal length"));
+        let err = load_input("rn=1,,cm-").unwrap_err();
+        assert_eq!((err.column, err.expected.as_str()), (6, "`=` or `-`"));
+        assert_eq!(load_input("=1").unwrap_err().column, 1);
+        assert_eq!(load_input("cm-2").unwrap_err().column, 4);
+        assert!(load_input("\n").is_err());
+        let input = load_input("rn=1,cm-,qp=3,cm=2\nqp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7\n").unwrap();
         assert_eq!(part2(&input), 145);
     }
 }
@@ -1,24 +1,22 @@
+use crate::direction::Direction;
+use crate::grid::Grid;
+use crate::image::{Overlay, Picture};
+use crate::parse::ParseError;
+use crate::solution::{Answer, Solution};
+use crate::viewer::{Frame, Palette, Rgb};
 use rayon::prelude::*;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
-pub type Input = Vec<Vec<char>>;
+pub type Input = Grid<char>;
 
 #[aoc_generator(day16)]
-pub fn load_input(input: &str) -> Input {
-    let mut output = vec![];
-    for line in input.lines() {
-        let temp: Vec<_> = line.chars().collect();
-        output.push(temp);
+pub fn load_input(input: &str) -> Result<Input, ParseError> {
+    let grid = Grid::parse_str(16, input, "./\\|-")?;
+    // The beam comes in at the top left, so there has to be a tile there
+    if grid.width() == 0 {
+        return Err(ParseError::new(16, 1, 1, "a tile", ""));
     }
-    output
-}
-
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
-pub enum Direction {
-    North,
-    East,
-    South,
-    West,
+    Ok(grid)
 }
 
 pub type LS = ((i64, i64), Direction);
@@ -50,60 +48,45 @@ impl LightState {
     pub fn step(
         &mut self,
         energized: &mut HashSet<(i64, i64)>,
-        map: &[Vec<char>],
+        map: &Grid<char>,
     ) -> (bool, Option<LightState>) {
-        let xmax = map[0].len() as i64;
-        let ymax = map.len() as i64;
-        let mut next_pos = self.position;
         energized.insert(self.position);
 
         // Look at current position in map to decide if we have to do something fancy or not
-        let tile = map[self.position.1 as usize][self.position.0 as usize];
+        let tile = map[(self.position.0 as usize, self.position.1 as usize)];
         let mut new_state = None;
         match tile {
             '.' => (),
             '/' => {
                 // mirror
-                let next_dir = match self.direction {
-                    Direction::North => Direction::East,
-                    Direction::East => Direction::North,
-                    Direction::South => Direction::West,
-                    Direction::West => Direction::South,
+                self.direction = if self.direction.is_vertical() {
+                    self.direction.turn_right()
+                } else {
+                    self.direction.turn_left()
                 };
-                self.direction = next_dir;
             }
             '\\' => {
                 // mirror
-                let next_dir = match self.direction {
-                    Direction::North => Direction::West,
-                    Direction::East => Direction::South,
-                    Direction::South => Direction::East,
-                    Direction::West => Direction::North,
+                self.direction = if self.direction.is_vertical() {
+                    self.direction.turn_left()
+                } else {
+                    self.direction.turn_right()
                 };
-                self.direction = next_dir;
             }
             '|' => {
                 // splitter
-                let next_dir = match self.direction {
-                    Direction::North => Direction::North,
-                    Direction::East => Direction::North,
-                    Direction::South => Direction::South,
-                    Direction::West => Direction::North,
-                };
-                self.direction = next_dir;
+                if !self.direction.is_vertical() {
+                    self.direction = Direction::North;
+                }
 
                 // Spawn a new `LightState` going South at self.position
                 new_state = Some(LightState::new(self.position, Direction::South));
             }
             '-' => {
                 // splitter
-                let next_dir = match self.direction {
-                    Direction::North => Direction::East,
-                    Direction::East => Direction::East,
-                    Direction::South => Direction::East,
-                    Direction::West => Direction::West,
-                };
-                self.direction = next_dir;
+                if self.direction.is_vertical() {
+                    self.direction = Direction::East;
+                }
 
                 // Spawn a new `LightState` going West at self.position
                 new_state = Some(LightState::new(self.position, Direction::West));
@@ -112,15 +95,10 @@ impl LightState {
         }
 
         // Now that we're oriented in the correct direction, move
-        match self.direction {
-            Direction::North => next_pos.1 -= 1,
-            Direction::East => next_pos.0 += 1,
-            Direction::South => next_pos.1 += 1,
-            Direction::West => next_pos.0 -= 1,
-        }
+        let next_pos = self.direction.offset(self.position, 1);
 
         // Are we still in bounds?
-        if next_pos.0 >= xmax || next_pos.0 < 0 || next_pos.1 >= ymax || next_pos.1 < 0 {
+        if !map.in_bounds(next_pos) {
             (false, new_state)
         } else {
             self.position = next_pos;
@@ -137,6 +115,15 @@ impl LightState {
 }
 
 pub fn simulate(starter: LightState, input: &Input) -> usize {
+    simulate_with(starter, input, |_, _| ())
+}
+
+/// `simulate`, calling `on_step` with the energized tiles and the live beams after every step.
+pub fn simulate_with(
+    starter: LightState,
+    input: &Input,
+    mut on_step: impl FnMut(&HashSet<(i64, i64)>, &[LightState]),
+) -> usize {
     let starter_state = starter.get_state();
     let mut energized: HashSet<(i64, i64)> = HashSet::new();
     let mut lights = vec![starter];
@@ -174,6 +161,7 @@ pub fn simulate(starter: LightState, input: &Input) -> usize {
 
         // Add new lights if any
         lights.append(&mut new_lights);
+        on_step(&energized, &lights);
     }
 
     energized.len()
@@ -188,8 +176,8 @@ pub fn part1(input: &Input) -> usize {
 #[aoc(day16, part2)]
 pub fn part2(input: &Input) -> usize {
     let mut start_states = vec![];
-    let xmax = input[0].len() as i64;
-    let ymax = input.len() as i64;
+    let xmax = input.width() as i64;
+    let ymax = input.height() as i64;
     for x in 0..xmax as usize {
         start_states.push(LightState::new((x as i64, 0), Direction::South));
         start_states.push(LightState::new((x as i64, ymax - 1), Direction::North));
@@ -202,22 +190,92 @@ pub fn part2(input: &Input) -> usize {
         .par_iter()
         .map(|s| simulate(s.clone(), input))
         .max()
-        .unwrap()
+        .unwrap_or(0)
 }
 
-#[allow(dead_code)]
-fn print_map(position: (i64, i64), input: &Input) {
-    for (y, line) in input.iter().enumerate() {
-        for (x, c) in line.iter().enumerate() {
-            if (x as i64, y as i64) == position {
-                print!("#");
-            } else {
-                print!("{}", c);
+/// The part 1 beam spreading through the contraption, one step per frame.
+pub fn beam_frames(input: &Input) -> Vec<Frame> {
+    let mut frames = vec![];
+    let starter = LightState::new((0, 0), Direction::East);
+    simulate_with(starter, input, |energized, lights| {
+        // Draw the head of each beam over empty space
+        let mut grid = input.clone();
+        for light in lights {
+            let pos = (light.position.0 as usize, light.position.1 as usize);
+            if grid[pos] == '.' {
+                grid[pos] = match light.direction {
+                    Direction::North => '^',
+                    Direction::East => '>',
+                    Direction::South => 'v',
+                    Direction::West => '<',
+                };
             }
         }
-        println!();
+        let caption = format!(
+            "step {}: {} energized, {} beams",
+            frames.len() + 1,
+            energized.len(),
+            lights.len()
+        );
+        let energized = energized.iter().map(|&(x, y)| (x as usize, y as usize));
+        frames.push(
+            Frame::new(grid)
+                .with_highlight(energized)
+                .with_caption(caption),
+        );
+    });
+    frames
+}
+
+/// How many times a part 1 beam passed through each tile.
+pub fn beam_heat(input: &Input) -> HashMap<(usize, usize), u64> {
+    let mut heat = HashMap::new();
+    let starter = LightState::new((0, 0), Direction::East);
+    *heat.entry((0, 0)).or_default() += 1;
+    simulate_with(starter, input, |_, lights| {
+        for light in lights {
+            let pos = (light.position.0 as usize, light.position.1 as usize);
+            *heat.entry(pos).or_default() += 1;
+        }
+    });
+    heat
+}
+
+pub struct Day16;
+
+impl Solution for Day16 {
+    type Input = Input;
+
+    const DAY: u32 = 16;
+
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        load_input(input)
+    }
+
+    fn part1(input: &Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Input) -> Answer {
+        part2(input).into()
+    }
+
+    fn frames(input: &Input) -> Vec<Frame> {
+        beam_frames(input)
+    }
+
+    fn palette() -> Palette {
+        Palette::new(&[('.', Rgb(60, 60, 60))]).with_highlight(Rgb(255, 200, 60))
+    }
+
+    fn picture(input: &Input) -> Option<Picture> {
+        let heatmap = Overlay::Heatmap {
+            values: beam_heat(input),
+            low: Rgb(90, 20, 20),
+            high: Rgb(255, 230, 80),
+        };
+        Some(Picture::new(input.clone(), Self::palette()).with_overlay(heatmap))
     }
-    println!();
 }
 
 #[cfg(test)]
@@ -228,14 +286,26 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2023/16a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 46);
+
+        let frames = beam_frames(&input);
+        assert_eq!(frames.last().unwrap().highlight.len(), 46);
+        assert_eq!(beam_heat(&input).len(), 46);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2023/16a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 51);
     }
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(load_input("").unwrap_err().expected, "a tile");
+        assert!(load_input("\n").is_err());
+        let input = load_input("\\").unwrap();
+        assert_eq!((part1(&input), part2(&input)), (1, 1));
+    }
 }
@@ -1,30 +1,34 @@
-use rayon::prelude::*;
-use std::collections::HashMap;
+use crate::direction::Direction;
+use crate::grid::Grid;
+use crate::image::{Overlay, Picture};
+use crate::parse::{self, ParseError};
+use crate::solution::{Answer, Solution};
+use crate::viewer::{Palette, Rgb};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use tracing::trace;
 
-pub type Input = Vec<Vec<u8>>;
+pub type Input = Grid<u8>;
 
 #[aoc_generator(day17)]
-pub fn load_input(input: &str) -> Input {
-    let mut output = vec![];
-    for line in input.lines() {
-        let temp: Vec<_> = line
-            .chars()
-            .map(|x| x.to_digit(10).unwrap() as u8)
-            .collect();
-        output.push(temp);
+pub fn load_input(input: &str) -> Result<Input, ParseError> {
+    let lines: Vec<_> = parse::lines(17, input).collect();
+    let grid = Grid::parse_with(lines.iter().copied(), "a digit", |x| {
+        x.to_digit(10).map(|d| d as u8)
+    })?;
+    if grid.width() == 0 {
+        return Err(ParseError::new(17, 1, 1, "a block", ""));
     }
-    output
-}
-
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
-pub enum Direction {
-    North,
-    East,
-    South,
-    West,
+    // Both crucibles have to be able to get from the top left block to the factory
+    let (width, height) = (grid.width(), grid.height());
+    if !Crucible::ULTRA.can_cross(width, height) || !Crucible::NORMAL.can_cross(width, height) {
+        let line = lines[height - 1];
+        return Err(line.error(&line.text[width - 1..], "a city both crucibles can cross"));
+    }
+    Ok(grid)
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct State {
     position: (usize, usize),
     direction: Direction,
@@ -39,30 +43,43 @@ impl State {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
-pub struct Path {
-    last_state: Option<State>,
-    cost: usize,
+/// How far a crucible has to go in a straight line before it can turn (or stop), and how far it
+/// can go before it has to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Crucible {
+    pub min_run: usize,
+    pub max_run: usize,
 }
 
-impl Path {
-    pub fn new() -> Self {
-        Self {
-            last_state: None,
-            cost: 0,
-        }
-    }
+impl Crucible {
+    pub const NORMAL: Crucible = Crucible {
+        min_run: 1,
+        max_run: 3,
+    };
+    pub const ULTRA: Crucible = Crucible {
+        min_run: 4,
+        max_run: 10,
+    };
 
-    pub fn push(&mut self, state: State, new_cost: usize) {
-        self.last_state = Some(state);
-        self.cost += new_cost;
+    /// Whether the crucible can get from one corner of a `width` by `height` city to the other.
+    /// Runs alternate between across and down, so if there's room for a run each way it can go
+    /// back and forth as often as it needs to, otherwise it only gets one run.
+    pub fn can_cross(self, width: usize, height: usize) -> bool {
+        let run = self.min_run..=self.max_run;
+        match (width - 1, height - 1) {
+            (0, 0) => true,
+            (0, n) | (n, 0) => run.contains(&n),
+            (x, y) => x >= self.min_run && y >= self.min_run,
+        }
     }
 }
 
-impl Default for Path {
-    fn default() -> Self {
-        Self::new()
-    }
+/// The cheapest way between two blocks and how much heat it loses.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Route {
+    pub heat_loss: usize,
+    /// Every block visited, starting with the start and ending with the goal.
+    pub path: Vec<(usize, usize)>,
 }
 
 // Returns optional new position and cost of the move
@@ -70,198 +87,145 @@ pub fn try_move(
     position: (usize, usize),
     direction: Direction,
     n: usize,
-    map: &[Vec<u8>],
+    map: &Grid<u8>,
 ) -> Option<((usize, usize), usize)> {
-    let xmax = map[0].len();
-    let ymax = map.len();
-
-    match direction {
-        Direction::North => {
-            if position.1 < n {
-                None
-            } else {
-                let mut cost = 0;
-                for i in 1..n + 1 {
-                    cost += map[position.1 - i][position.0] as usize;
-                }
-                Some(((position.0, position.1 - n), cost))
-            }
-        }
-        Direction::East => {
-            if position.0 + n >= xmax {
-                None
-            } else {
-                let mut cost = 0;
-                for i in 1..n + 1 {
-                    cost += map[position.1][position.0 + i] as usize;
-                }
-                Some(((position.0 + n, position.1), cost))
-            }
-        }
-        Direction::South => {
-            if position.1 + n >= ymax {
-                None
-            } else {
-                let mut cost = 0;
-                for i in 1..n + 1 {
-                    cost += map[position.1 + i][position.0] as usize;
-                }
-                Some(((position.0, position.1 + n), cost))
-            }
-        }
-        Direction::West => {
-            if position.0 < n {
-                None
-            } else {
-                let mut cost = 0;
-                for i in 1..n + 1 {
-                    cost += map[position.1][position.0 - i] as usize;
-                }
-                Some(((position.0 - n, position.1), cost))
-            }
-        }
-    }
+    let new_pos = map.step(position, direction, n)?;
+    let cost = (1..n + 1)
+        .map(|i| map[map.step(position, direction, i).unwrap()] as usize)
+        .sum();
+    Some((new_pos, cost))
 }
 
-pub fn next_states(state: State, map: &[Vec<u8>], part2: bool) -> Vec<(State, usize)> {
+// Every state one straight run after `state`, with the run's length and cost. A crucible always
+// turns at the end of a run, since carrying straight on is just a longer run.
+pub fn next_states(state: State, map: &Grid<u8>, crucible: Crucible) -> Vec<(State, usize, usize)> {
     let mut next_states = vec![];
-
-    let dists = if part2 {
-        vec![4, 5, 6, 7, 8, 9, 10]
-    } else {
-        vec![1, 2, 3]
-    };
-
-    // Can turn left or right
-    match state.direction {
-        Direction::North => {
-            let dirs = vec![Direction::West, Direction::East];
-            for dir in dirs {
-                for dist in &dists {
-                    if let Some((new_pos, cost)) = try_move(state.position, dir, *dist, map) {
-                        next_states.push((State::new(new_pos, dir), cost));
-                    }
-                }
-            }
-        }
-        Direction::East => {
-            let dirs = vec![Direction::North, Direction::South];
-            for dir in dirs {
-                for dist in &dists {
-                    if let Some((new_pos, cost)) = try_move(state.position, dir, *dist, map) {
-                        next_states.push((State::new(new_pos, dir), cost));
-                    }
-                }
-            }
-        }
-        Direction::South => {
-            let dirs = vec![Direction::East, Direction::West];
-            for dir in dirs {
-                for dist in &dists {
-                    if let Some((new_pos, cost)) = try_move(state.position, dir, *dist, map) {
-                        next_states.push((State::new(new_pos, dir), cost));
-                    }
-                }
-            }
-        }
-        Direction::West => {
-            let dirs = vec![Direction::South, Direction::North];
-            for dir in dirs {
-                for dist in &dists {
-                    if let Some((new_pos, cost)) = try_move(state.position, dir, *dist, map) {
-                        next_states.push((State::new(new_pos, dir), cost));
-                    }
-                }
+    for dir in [state.direction.turn_left(), state.direction.turn_right()] {
+        for dist in crucible.min_run..crucible.max_run + 1 {
+            if let Some((new_pos, cost)) = try_move(state.position, dir, dist, map) {
+                next_states.push((State::new(new_pos, dir), dist, cost));
             }
         }
     }
     next_states
 }
 
-pub fn solve(input: &Input, part2: bool) -> usize {
-    let map = input.clone();
-    let xmax = map[0].len();
-    let ymax = map.len();
-    let start0 = State::new((0, 0), Direction::East);
-    let start1 = State::new((0, 0), Direction::South);
-    let mut next_states_and_costs = next_states(start0, &map, part2);
-    next_states_and_costs.append(&mut next_states(start1, &map, part2));
-
-    // Keep track of the minimum cost path to get to every (x, y)
-    let mut minpaths: HashMap<State, Path> = HashMap::new();
-    for (state, cost) in next_states_and_costs {
-        let mut path = Path::new();
-        path.push(state, cost);
-        minpaths.insert(state, path);
+/// A* from `start` to `goal`, or `None` if the crucible can't get there.
+pub fn route(
+    input: &Input,
+    crucible: Crucible,
+    start: (usize, usize),
+    goal: (usize, usize),
+) -> Option<Route> {
+    if start == goal {
+        return Some(Route {
+            heat_loss: 0,
+            path: vec![start],
+        });
     }
+    // Every block costs at least this much, so it times the distance left never overestimates
+    let cheapest = input.rows().flatten().copied().min().unwrap_or(0) as usize;
+    let estimate = |(x, y): (usize, usize)| cheapest * (x.abs_diff(goal.0) + y.abs_diff(goal.1));
 
-    let mut last_minpath_state;
-    let mut niters: usize = 0;
-    loop {
-        println!("iter: {}", niters);
-        last_minpath_state = minpaths.clone();
-        let candidate_paths: Vec<_> = minpaths
-            .par_iter()
-            .flat_map(|(_key, path)| {
-                //for path in minpaths.values() {
-                // Where can this path go to?
-                let state = path.last_state.unwrap();
-                let next_states_and_costs = next_states(state, &map, part2);
-
-                // Keep track of all these (maybe) new paths to see if any are worth keeping
-                let mut output = vec![];
-                for (new_state, new_cost) in next_states_and_costs {
-                    let mut candidate_path = *path;
-                    candidate_path.push(new_state, new_cost);
-                    output.push(candidate_path);
-                }
-                output
-            })
-            .collect();
+    let mut costs: HashMap<State, usize> = HashMap::new();
+    // How each state was reached: the state before and the length of the run
+    let mut came_from: HashMap<State, (State, usize)> = HashMap::new();
+    let mut queue = BinaryHeap::new();
+    // Facing east or south means the first run can turn to any of the four directions
+    for direction in [Direction::East, Direction::South] {
+        let state = State::new(start, direction);
+        costs.insert(state, 0);
+        queue.push(Reverse((estimate(start), 0, state)));
+    }
 
-        // Insert new minpaths into minpaths
-        for cpath in candidate_paths {
-            let cstate = cpath.last_state.unwrap();
-            if let Some(existing_path) = minpaths.get(&cstate) {
-                // Compare if new path has lower cost
-                if existing_path.cost > cpath.cost {
-                    // If the new path is better, keep it!
-                    minpaths.insert(cstate, cpath);
+    while let Some(Reverse((_, cost, state))) = queue.pop() {
+        if costs.get(&state).is_some_and(|&c| c < cost) {
+            // Already found a cheaper way here
+            continue;
+        }
+        if state.position == goal {
+            let mut path = vec![];
+            let mut at = state;
+            while let Some(&(before, run)) = came_from.get(&at) {
+                for i in 0..run {
+                    path.push(input.step(at.position, at.direction.reverse(), i).unwrap());
                 }
-            } else {
-                minpaths.insert(cstate, cpath);
+                at = before;
             }
+            path.push(start);
+            path.reverse();
+            trace!(states = costs.len(), "route found");
+            return Some(Route {
+                heat_loss: cost,
+                path,
+            });
         }
-        if minpaths == last_minpath_state {
-            break;
-        } else {
-            niters += 1;
+        for (next, run, step_cost) in next_states(state, input, crucible) {
+            let next_cost = cost + step_cost;
+            if costs.get(&next).is_none_or(|&c| next_cost < c) {
+                costs.insert(next, next_cost);
+                came_from.insert(next, (state, run));
+                queue.push(Reverse((
+                    next_cost + estimate(next.position),
+                    next_cost,
+                    next,
+                )));
+            }
         }
     }
+    None
+}
 
-    let endstate0 = State::new((xmax - 1, ymax - 1), Direction::East);
-    let endstate1 = State::new((xmax - 1, ymax - 1), Direction::South);
-    let mut solution = 0;
-    if let Some(best_path) = minpaths.get(&endstate0) {
-        solution = best_path.cost;
-    }
-
-    if let Some(best_path) = minpaths.get(&endstate1) {
-        if best_path.cost < solution {
-            solution = best_path.cost;
-        }
-    }
-    solution
+fn corner_to_corner(input: &Input, crucible: Crucible) -> Route {
+    let goal = (input.width() - 1, input.height() - 1);
+    route(input, crucible, (0, 0), goal)
+        .expect("load_input checks the crucibles can cross the city")
 }
 
 #[aoc(day17, part1)]
 pub fn part1(input: &Input) -> usize {
-    solve(input, false)
+    corner_to_corner(input, Crucible::NORMAL).heat_loss
 }
 
 #[aoc(day17, part2)]
 pub fn part2(input: &Input) -> usize {
-    solve(input, true)
+    corner_to_corner(input, Crucible::ULTRA).heat_loss
+}
+
+pub struct Day17;
+
+impl Solution for Day17 {
+    type Input = Input;
+
+    const DAY: u32 = 17;
+
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        load_input(input)
+    }
+
+    fn part1(input: &Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Input) -> Answer {
+        part2(input).into()
+    }
+
+    fn picture(input: &Input) -> Option<Picture> {
+        // Darker blocks lose more heat
+        let grid = input.map(|&d| char::from_digit(d as u32, 10).unwrap());
+        let shades: Vec<_> = (1..10)
+            .map(|d| {
+                let v = 255 - 22 * d as u8;
+                (char::from_digit(d, 10).unwrap(), Rgb(v, v, v))
+            })
+            .collect();
+        let path = Overlay::Path {
+            points: corner_to_corner(input, Crucible::ULTRA).path,
+            color: Rgb(230, 40, 40),
+        };
+        Some(Picture::new(grid, Palette::new(&shades)).with_overlay(path))
+    }
 }
 
 #[cfg(test)]
@@ -272,18 +236,65 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2023/17a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 102);
+
+        let best = corner_to_corner(&input, Crucible::NORMAL);
+        let cost: usize = best.path[1..].iter().map(|&p| input[p] as usize).sum();
+        assert_eq!(cost, 102);
+        assert!(best
+            .path
+            .windows(2)
+            .all(|w| input.neighbors4(w[0]).any(|n| n == w[1])));
+
+        // Going back loses heat on different blocks
+        let back = route(&input, Crucible::NORMAL, (12, 12), (0, 0)).unwrap();
+        assert_eq!(back.path.first(), Some(&(12, 12)));
+        assert_eq!(back.path.last(), Some(&(0, 0)));
+        let cost: usize = back.path[1..].iter().map(|&p| input[p] as usize).sum();
+        assert_eq!(back.heat_loss, cost);
+
+        // Has to go straight for too long to ever stop on the next block
+        let stuck = Crucible {
+            min_run: 20,
+            max_run: 20,
+        };
+        assert_eq!(route(&input, stuck, (0, 0), (1, 0)), None);
+
+        // Already there, even if it can't move at all
+        let here = Route {
+            heat_loss: 0,
+            path: vec![(3, 4)],
+        };
+        assert_eq!(route(&input, stuck, (3, 4), (3, 4)), Some(here));
+    }
+
+    #[test]
+    fn test_can_cross() {
+        assert!(load_input("").is_err());
+        // Too narrow for an ultra crucible to turn, or too long for one run
+        let err = load_input("111111\n111111").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 6));
+        assert!(load_input(&"1".repeat(12)).is_err());
+        // One run too few for the normal crucible, and too many for the ultra one
+        assert!(load_input("11111").is_err());
+        let input = load_input(&["11111"; 5].join("\n")).unwrap();
+        assert_eq!((part1(&input), part2(&input)), (8, 8));
+        let input = load_input("1").unwrap();
+        assert_eq!((part1(&input), part2(&input)), (0, 0));
+        assert!(Crucible::ULTRA.can_cross(5, 5));
+        assert!(!Crucible::ULTRA.can_cross(5, 4));
+        assert!(Crucible::NORMAL.can_cross(2, 7));
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2023/17a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 94);
 
         let input = read_to_string("input/2023/17b.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 71);
     }
 }
@@ -1,158 +1,169 @@
-use rayon::prelude::*;
-use std::collections::{HashMap, HashSet};
-
-pub type Input = Vec<(char, usize, String)>;
-
-pub enum Direction {
-    North,
-    East,
-    South,
-    West,
+use crate::direction::Direction;
+use crate::grid::Grid;
+use crate::image::{Overlay, Picture};
+use crate::parse::{self, ParseError};
+use crate::solution::{Answer, Solution};
+use crate::viewer::{Palette, Rgb};
+use tracing::debug;
+
+/// Dig `len` metres towards `dir`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Dig {
+    pub dir: Direction,
+    pub len: i128,
 }
 
+/// Each line of the plan: the step as written, and the real one hidden in the colour.
+pub type Input = Vec<(Dig, Dig)>;
+
 #[aoc_generator(day18)]
-pub fn load_input(input: &str) -> Input {
+pub fn load_input(input: &str) -> Result<Input, ParseError> {
     let mut output = vec![];
-    for line in input.lines() {
-        let temp: Vec<_> = line.split(' ').collect();
-        let dir = temp[0].chars().next().unwrap();
-        let len = temp[1].parse::<usize>().unwrap();
-        let hash = temp[2].to_string();
-        output.push((dir, len, hash));
+    for line in parse::lines(18, input) {
+        let (dir, rest) = line.split_once(line.text, " ")?;
+        let (len, hex) = line.split_once(rest, " ")?;
+        let dir = match dir {
+            "U" | "D" | "L" | "R" => Direction::try_from(dir.chars().next().unwrap()).unwrap(),
+            _ => return Err(line.error(dir, "one of `UDLR`")),
+        };
+        let len = line.parse::<i128>(len, "a length")?;
+        let hex = line.strip_prefix(hex, "(#")?;
+        let hex = line.strip_suffix(hex, ")")?;
+        output.push((Dig { dir, len }, decode_hex(&line, hex)?));
     }
-    output
+    Ok(output)
 }
 
-pub fn create_trench(input: &Input) -> HashMap<(i64, i64), char> {
-    let mut map: HashMap<(i64, i64), char> = HashMap::new();
-    let mut pos = (0, 0);
-    map.insert(pos, '#');
-    for (dir, len, _hex) in input {
-        match dir {
-            'U' => {
-                for _ in 0..*len {
-                    pos.1 -= 1;
-                    map.insert(pos, '#');
-                }
-            }
-            'R' => {
-                for _ in 0..*len {
-                    pos.0 += 1;
-                    map.insert(pos, '#');
-                }
-            }
-            'D' => {
-                for _ in 0..*len {
-                    pos.1 += 1;
-                    map.insert(pos, '#');
-                }
-            }
-            'L' => {
-                for _ in 0..*len {
-                    pos.0 -= 1;
-                    map.insert(pos, '#');
-                }
-            }
-            _ => (),
-        }
+// Five hex digits of distance, then one digit for the direction
+fn decode_hex(line: &parse::Line, hex: &str) -> Result<Dig, ParseError> {
+    if hex.len() != 6 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(line.error(hex, "six hex digits"));
     }
-    map
+    let len = i128::from_str_radix(&hex[..5], 16).unwrap();
+    let dir = match &hex[5..] {
+        "0" => Direction::East,
+        "1" => Direction::South,
+        "2" => Direction::West,
+        "3" => Direction::North,
+        _ => return Err(line.error(&hex[5..], "a direction digit from 0 to 3")),
+    };
+    Ok(Dig { dir, len })
 }
 
-pub fn fill_trench(map: &mut HashMap<(i64, i64), char>) {
-    let xmin: i64 = *map.keys().map(|(x, _)| x).min().unwrap();
-    let ymin: i64 = *map.keys().map(|(_, y)| y).min().unwrap();
-    let xmax: i64 = *map.keys().map(|(x, _)| x).max().unwrap();
-    let ymax: i64 = *map.keys().map(|(_, y)| y).max().unwrap();
-
-    for x in xmin..xmax + 1 {
-        let mut toggle = 0;
-        let mut last = (x, ymin - 1);
-        let mut lastlast = (x, ymin - 2);
-        for y in ymin..ymax + 1 {
-            if map.get(&(x, y)).is_none()
-                && map.get(&last).is_some()
-                && map.get(&lastlast).is_none()
-            {
-                toggle ^= 1;
-            }
-            if toggle == 1 {
-                map.insert((x, y), '#');
-            }
-        }
+/// Cubic metres dug out by following `plan` and then digging out the inside of the loop.
+///
+/// The shoelace formula gives the area enclosed by the path through the middle of the trench.
+/// Pick's theorem turns that into the number of whole cubes strictly inside it, and the trench
+/// itself adds one cube per metre dug.
+pub fn lagoon_volume(plan: impl IntoIterator<Item = Dig>) -> i128 {
+    let mut pos = (0i128, 0i128);
+    let mut twice_area = 0;
+    let mut boundary = 0;
+    for Dig { dir, len } in plan {
+        let (dx, dy) = dir.delta();
+        let next = (pos.0 + dx as i128 * len, pos.1 + dy as i128 * len);
+        twice_area += pos.0 * next.1 - next.0 * pos.1;
+        boundary += len;
+        pos = next;
     }
+    let interior = twice_area.abs() / 2 - boundary / 2 + 1;
+    interior + boundary
+}
+
+#[aoc(day18, part1)]
+pub fn part1(input: &Input) -> i128 {
+    lagoon_volume(input.iter().map(|&(plan, _)| plan))
 }
 
-pub fn get_neighbors(pos: (i64, i64)) -> Vec<(i64, i64)> {
-    let (x, y) = pos;
-    vec![(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)]
+#[aoc(day18, part2)]
+pub fn part2(input: &Input) -> i128 {
+    lagoon_volume(input.iter().map(|&(_, hex)| hex))
+}
+
+/// Where each dig ends, starting from the origin.
+fn corners(plan: &[Dig]) -> Vec<(i64, i64)> {
+    let mut pos = (0, 0);
+    let mut corners = vec![pos];
+    for Dig { dir, len } in plan {
+        pos = dir.offset(pos, *len as i64);
+        corners.push(pos);
+    }
+    corners
 }
 
-pub fn waterfill(start: (i64, i64), map: &mut HashMap<(i64, i64), char>) {
-    let mut frontier = get_neighbors(start);
-    loop {
-        //print_map(&map);
-        //println!();
-        if frontier.is_empty() {
-            break;
-        } else {
-            println!("frontier.len(): {}", frontier.len());
+/// The lagoon dug by `plan` as a grid, with the corners of the trench moved onto it.
+fn lagoon_grid(plan: &[Dig]) -> (Grid<char>, Vec<(usize, usize)>) {
+    let corners = corners(plan);
+    let xmin = corners.iter().map(|c| c.0).min().unwrap();
+    let xmax = corners.iter().map(|c| c.0).max().unwrap();
+    let ymin = corners.iter().map(|c| c.1).min().unwrap();
+    let ymax = corners.iter().map(|c| c.1).max().unwrap();
+    let corners: Vec<_> = corners
+        .iter()
+        .map(|&(x, y)| ((x - xmin) as usize, (y - ymin) as usize))
+        .collect();
+    let mut grid = Grid::new((xmax - xmin + 1) as usize, (ymax - ymin + 1) as usize, '.');
+
+    // Scan each row, filling between pairs of crossings with the vertical trenches. Counting
+    // each trench from its top end up to but not including its bottom end means a row through
+    // a corner only crosses once when the trench carries on the other side.
+    for y in 0..grid.height() {
+        let mut crossings: Vec<usize> = corners
+            .windows(2)
+            .filter(|w| w[0].0 == w[1].0 && w[0].1.min(w[1].1) <= y && y < w[0].1.max(w[1].1))
+            .map(|w| w[0].0)
+            .collect();
+        crossings.sort_unstable();
+        for pair in crossings.chunks_exact(2) {
+            for x in pair[0]..=pair[1] {
+                grid[(x, y)] = '#';
+            }
         }
-        let mut new_frontier = HashSet::new();
-        for pos in &frontier {
-            if !map.contains_key(&pos) {
-                // Don't explore something we've explored before
-                let neighbors = get_neighbors(*pos);
-                let neighbors: Vec<_> = neighbors
-                    .into_iter()
-                    .filter(|n| !map.contains_key(n))
-                    .collect();
-                for n in neighbors {
-                    if !map.contains_key(&n) && !frontier.contains(&n) {
-                        // If truly new, add to frontier
-                        new_frontier.insert(n);
-                    }
-                }
-                map.insert(*pos, '#');
+    }
+    // And the trench itself, some of which is along the rows
+    for w in corners.windows(2) {
+        for x in w[0].0.min(w[1].0)..=w[0].0.max(w[1].0) {
+            for y in w[0].1.min(w[1].1)..=w[0].1.max(w[1].1) {
+                grid[(x, y)] = '#';
             }
         }
-
-        frontier = new_frontier.into_iter().collect();
     }
+    (grid, corners)
 }
 
-#[aoc(day18, part1)]
-pub fn part1(input: &Input) -> usize {
-    let mut map = create_trench(input);
-    //fill_trench(&mut map);
-    waterfill((1, 1), &mut map);
-    print_map(&map);
-    map.iter().count()
+/// The part 1 lagoon with the dig plan traced around its edge.
+pub fn lagoon_picture(input: &Input) -> Picture {
+    let plan: Vec<Dig> = input.iter().map(|&(plan, _)| plan).collect();
+    let (grid, corners) = lagoon_grid(&plan);
+    debug!("lagoon:\n{}", grid);
+    let palette = Palette::new(&[('#', Rgb(110, 80, 50)), ('.', Rgb(20, 20, 20))]);
+    Picture::new(grid, palette).with_overlay(Overlay::Path {
+        points: corners,
+        color: Rgb(120, 200, 255),
+    })
 }
 
-pub fn parse_hex(input: &Input) -> Vec<(Direction, usize)> {
-    for (_, _, inst) in input {}
-    vec![]
-}
+pub struct Day18;
 
-#[aoc(day18, part2)]
-pub fn part2(input: &Input) -> usize {
-    0
-}
+impl Solution for Day18 {
+    type Input = Input;
 
-#[allow(dead_code)]
-fn print_map(map: &HashMap<(i64, i64), char>) {
-    let xmax = *map.keys().map(|(x, _)| x).max().unwrap();
-    let ymax = *map.keys().map(|(_, y)| y).max().unwrap();
-    for y in 0..ymax + 1 {
-        for x in 0..121 {
-            if let Some(c) = map.get(&(x, y)) {
-                print!("{}", c);
-            } else {
-                print!(".");
-            }
-        }
-        println!();
+    const DAY: u32 = 18;
+
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        load_input(input)
+    }
+
+    fn part1(input: &Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Input) -> Answer {
+        part2(input).into()
+    }
+
+    fn picture(input: &Input) -> Option<Picture> {
+        Some(lagoon_picture(input))
     }
 }
 
@@ -164,14 +175,36 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2023/18a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 62);
+
+        // A 3x3 square is all trench bar the middle
+        let square = load_input("R 2 (#000000)\nD 2 (#000000)\nL 2 (#000000)\nU 2 (#000000)");
+        assert_eq!(part1(&square.unwrap()), 9);
+        assert_eq!(load_input("R 2 (#00000g)").unwrap_err().column, 7);
+
+        // The picture agrees with the volume, wherever the loop is
+        let count = |input: &Input| {
+            let plan: Vec<_> = input.iter().map(|&(plan, _)| plan).collect();
+            let (grid, _) = lagoon_grid(&plan);
+            grid.rows().flatten().filter(|&&c| c == '#').count() as i128
+        };
+        assert_eq!(count(&input), 62);
+        let square = load_input("L 2 (#000000)\nU 2 (#000000)\nR 2 (#000000)\nD 2 (#000000)");
+        assert_eq!(count(&square.unwrap()), 9);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2023/18a.txt").unwrap();
-        let input = load_input(&input);
-        assert_eq!(part2(&input), 94);
+        let input = load_input(&input).unwrap();
+        assert_eq!(
+            input[0].1,
+            Dig {
+                dir: Direction::East,
+                len: 461937
+            }
+        );
+        assert_eq!(part2(&input), 952408144115);
     }
 }
@@ -1,56 +1,136 @@
-use aoc_helpers::interval::Interval;
-use aoc_helpers::tree::Tree;
-use std::collections::HashMap;
+use crate::parse::{self, ParseError};
+use crate::solution::{Answer, Solution};
+use std::collections::{HashMap, HashSet};
 use std::fmt;
+use tracing::warn;
 
-pub type Input = (HashMap<String, Vec<Rule>>, Vec<Item>);
+pub type Input = (HashMap<String, Vec<Rule>>, Attributes, Vec<Item>);
 
-#[derive(Copy, Clone, Debug)]
+/// A part, with a rating for each attribute.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Item {
-    x: usize,
-    m: usize,
-    a: usize,
-    s: usize,
+    ratings: Vec<(String, i64)>,
 }
 
 impl Item {
-    pub fn rating(&self) -> usize {
-        self.x + self.m + self.a + self.s
+    pub fn new(ratings: &[(&str, i64)]) -> Self {
+        let ratings = ratings.iter().map(|(a, r)| (a.to_string(), *r)).collect();
+        Self { ratings }
+    }
+
+    pub fn rating(&self) -> i64 {
+        self.ratings.iter().map(|(_, r)| r).sum()
+    }
+
+    pub fn get(&self, attr: &str) -> Option<i64> {
+        self.ratings
+            .iter()
+            .find(|(a, _)| a == attr)
+            .map(|(_, r)| *r)
+    }
+}
+
+/// What parts are rated on, and the lowest and highest rating for each.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Attributes {
+    names: Vec<String>,
+    ranges: Vec<(i64, i64)>,
+}
+
+/// Ratings run from 1 to 4000 unless `Attributes::with_range` says otherwise.
+pub const DEFAULT_RANGE: (i64, i64) = (1, 4000);
+
+impl Attributes {
+    pub fn new(names: &[&str]) -> Self {
+        Self {
+            names: names.iter().map(|n| n.to_string()).collect(),
+            ranges: vec![DEFAULT_RANGE; names.len()],
+        }
     }
 
-    pub fn get(&self, attr: &str) -> usize {
-        match attr {
-            "x" => self.x,
-            "m" => self.m,
-            "a" => self.a,
-            "s" => self.s,
-            _ => panic!("invalid attribute"),
+    pub fn with_range(mut self, name: &str, lo: i64, hi: i64) -> Result<Self, String> {
+        let i = self
+            .index(name)
+            .ok_or_else(|| format!("no attribute called `{}`", name))?;
+        if lo > hi {
+            return Err(format!("`{}` can't run from {} down to {}", name, lo, hi));
+        }
+        self.ranges[i] = (lo, hi);
+        if self.bounds().volume().is_none() {
+            return Err(format!(
+                "too many parts to count with `{}` at {}..={}",
+                name, lo, hi
+            ));
         }
+        Ok(self)
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn index(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|n| n == name)
+    }
+
+    /// Every possible part.
+    pub fn bounds(&self) -> HyperRect {
+        HyperRect::new(self.ranges.clone())
     }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum RuleType {
     LessThan,
+    LessOrEqual,
     GreaterThan,
+    GreaterOrEqual,
+    Equal,
     GoTo,
 }
 
+impl RuleType {
+    /// The comparisons, longest first so `<=` isn't read as `<`.
+    const OPERATORS: [(&'static str, RuleType); 5] = [
+        ("<=", RuleType::LessOrEqual),
+        (">=", RuleType::GreaterOrEqual),
+        ("==", RuleType::Equal),
+        ("<", RuleType::LessThan),
+        (">", RuleType::GreaterThan),
+    ];
+
+    pub fn symbol(&self) -> &'static str {
+        Self::OPERATORS
+            .iter()
+            .find(|(_, t)| t == self)
+            .map_or("", |(op, _)| op)
+    }
+
+    /// The range of ratings that pass the comparison against `value`. Nothing is below
+    /// `i64::MIN` or above `i64::MAX`, which gives an empty range.
+    pub fn passing(&self, value: i64) -> (i64, i64) {
+        const NOTHING: (i64, i64) = (1, 0);
+        match self {
+            RuleType::LessThan => value.checked_sub(1).map_or(NOTHING, |v| (i64::MIN, v)),
+            RuleType::LessOrEqual => (i64::MIN, value),
+            RuleType::GreaterThan => value.checked_add(1).map_or(NOTHING, |v| (v, i64::MAX)),
+            RuleType::GreaterOrEqual => (value, i64::MAX),
+            RuleType::Equal => (value, value),
+            RuleType::GoTo => (i64::MIN, i64::MAX),
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Rule {
     check: Option<String>,
     rule_type: RuleType,
-    value: Option<usize>,
+    value: Option<i64>,
     goto: String,
 }
 
 impl Rule {
-    pub fn new(
-        check: Option<String>,
-        rule_type: RuleType,
-        value: Option<usize>,
-        goto: &str,
-    ) -> Self {
+    pub fn new(check: Option<String>, rule_type: RuleType, value: Option<i64>, goto: &str) -> Self {
         Self {
             check,
             rule_type,
@@ -58,289 +138,607 @@ impl Rule {
             goto: goto.to_string(),
         }
     }
+
+    /// Whether this rule sends `item` on. A part without the attribute never matches.
+    pub fn matches(&self, item: &Item) -> bool {
+        match (&self.check, self.value) {
+            (Some(check), Some(value)) => item.get(check).is_some_and(|r| {
+                let (lo, hi) = self.rule_type.passing(value);
+                (lo..=hi).contains(&r)
+            }),
+            _ => true,
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let (Some(check), Some(value)) = (&self.check, self.value) {
+            write!(f, "{}{}{}:", check, self.rule_type.symbol(), value)?;
+        }
+        write!(f, "{}", self.goto)
+    }
 }
 
 #[aoc_generator(day19)]
-pub fn load_input(input: &str) -> Input {
+pub fn load_input(input: &str) -> Result<Input, ParseError> {
     let mut parts = false;
     let mut rules: HashMap<String, Vec<Rule>> = HashMap::new();
     let mut items = vec![];
-    for line in input.lines() {
+    // Attributes checked by rules, and named by each part, so they can be compared at the end
+    let mut checks = vec![];
+    let mut named = vec![];
+    for line in parse::lines(19, input) {
         if parts {
             // Handle the Items
-            let temp = line.split([',', '=']).collect::<Vec<_>>();
-            let x = temp[1].parse::<usize>().unwrap();
-            let m = temp[3].parse::<usize>().unwrap();
-            let a = temp[5].parse::<usize>().unwrap();
-            let s = temp[7].trim_end_matches('}').parse::<usize>().unwrap();
-            items.push(Item { x, m, a, s });
-        } else if line.is_empty() {
+            let fields = line.strip_prefix(line.text, "{")?;
+            let fields = line.strip_suffix(fields, "}")?;
+            let mut ratings = vec![];
+            let mut names = vec![];
+            for field in fields.split(',') {
+                let (name, value) = line.split_once(field, "=")?;
+                if name.is_empty() || names.contains(&name) {
+                    return Err(line.error(name, "a new attribute name"));
+                }
+                ratings.push((name, line.parse::<i64>(value, "a rating")?));
+                names.push(name);
+            }
+            named.push((line, names));
+            items.push(Item::new(&ratings));
+        } else if line.text.is_empty() {
             // Now switch to parts
             parts = true;
         } else {
             // Parse the Rules
-            let temp = line.split('{').collect::<Vec<_>>();
-            let name = temp[0].to_string();
-            let linerules = temp[1].split(',').collect::<Vec<_>>();
+            let (name, linerules) = line.split_once(line.text, "{")?;
+            let linerules = line.strip_suffix(linerules, "}")?;
             let mut _rules = vec![];
-            for rule in linerules {
-                let temp2 = rule.split(':').collect::<Vec<_>>();
-                if temp2.len() > 1 {
-                    let goto = temp2[1];
-                    let mut rule_type = RuleType::GreaterThan;
-                    if temp2[0].find('<').is_some() {
-                        // Less than rule
-                        rule_type = RuleType::LessThan;
+            for rule in linerules.split(',') {
+                if let Some((cond, goto)) = rule.split_once(':') {
+                    let Some((i, op, rule_type)) = RuleType::OPERATORS
+                        .iter()
+                        .filter_map(|(op, t)| cond.find(op).map(|i| (i, *op, *t)))
+                        .min_by_key(|(i, op, _)| (*i, usize::MAX - op.len()))
+                    else {
+                        return Err(line.error_after(cond, "`<`, `>`, `<=`, `>=` or `==`"));
+                    };
+                    let check = &cond[..i];
+                    if check.is_empty() {
+                        return Err(line.error(check, "an attribute name"));
                     }
-                    let temp3 = temp2[0].split(['<', '>']).collect::<Vec<_>>();
-                    let check = Some(temp3[0].to_string());
-                    let value = Some(temp3[1].parse::<usize>().unwrap());
-                    _rules.push(Rule::new(check, rule_type, value, goto));
+                    checks.push((line, check));
+                    let value = line.parse::<i64>(&cond[i + op.len()..], "a number")?;
+                    _rules.push(Rule::new(
+                        Some(check.to_string()),
+                        rule_type,
+                        Some(value),
+                        goto,
+                    ));
                 } else {
                     // Length 1 rule is just a goto
-                    let goto = temp2[0].trim_end_matches('}');
-                    _rules.push(Rule::new(None, RuleType::GoTo, None, goto));
+                    _rules.push(Rule::new(None, RuleType::GoTo, None, rule));
                 }
             }
-            rules.insert(name, _rules);
+            rules.insert(name.to_string(), _rules);
         }
     }
-    (rules, items)
-}
 
-pub fn check_accepted(item: Item, rules: &HashMap<String, Vec<Rule>>) -> bool {
-    let mut current = String::from("in");
-    loop {
-        let ruleset = rules.get(&current).unwrap();
-        for rule in ruleset {
-            match rule.rule_type {
-                RuleType::LessThan => {
-                    let value = item.get(rule.check.as_ref().unwrap());
-                    if value < rule.value.unwrap() {
-                        current = rule.goto.clone();
-                        break;
-                    }
-                }
-                RuleType::GreaterThan => {
-                    let value = item.get(rule.check.as_ref().unwrap());
-                    if value > rule.value.unwrap() {
-                        current = rule.goto.clone();
-                        break;
-                    }
-                }
-                RuleType::GoTo => {
-                    current = rule.goto.clone();
-                    break;
-                }
+    // The first part says what the attributes are, or failing that the rules do
+    let mut attributes: Vec<&str> = match named.first() {
+        Some((_, names)) => names.clone(),
+        None => vec![],
+    };
+    for (line, names) in &named {
+        if let Some(extra) = names.iter().find(|n| !attributes.contains(n)) {
+            return Err(line.error(extra, "the same attributes as the first part"));
+        }
+        if names.len() < attributes.len() {
+            return Err(line.error_after(line.text, "the same attributes as the first part"));
+        }
+    }
+    for &(line, check) in &checks {
+        if !attributes.contains(&check) {
+            if !named.is_empty() {
+                return Err(line.error(check, "an attribute the parts have"));
             }
+            attributes.push(check);
         }
+    }
+    // part2 counts every part in the bounds, so that count has to fit
+    if let Some(k) = (1..=attributes.len()).find(|&k| {
+        Attributes::new(&attributes[..k])
+            .bounds()
+            .volume()
+            .is_none()
+    }) {
+        let expected = "few enough attributes that the parts can be counted";
+        return Err(match named.first() {
+            Some((line, names)) => line.error(names[k - 1], expected),
+            None => {
+                let (line, check) = checks
+                    .iter()
+                    .find(|(_, c)| *c == attributes[k - 1])
+                    .unwrap();
+                line.error(check, expected)
+            }
+        });
+    }
+    Ok((rules, Attributes::new(&attributes), items))
+}
 
-        if current == "A" {
-            return true;
-        } else if current == "R" {
-            return false;
+/// Run `item` through the workflows from `in`. Errors if it's sent to a workflow that doesn't
+/// exist, falls off the end of one, or comes back round to one it's already been through (and
+/// so would go round forever).
+pub fn check_accepted(item: &Item, rules: &HashMap<String, Vec<Rule>>) -> Result<bool, String> {
+    let mut current = "in";
+    let mut seen = HashSet::new();
+    loop {
+        match current {
+            "A" => return Ok(true),
+            "R" => return Ok(false),
+            _ => (),
         }
+        if !seen.insert(current) {
+            return Err(format!("loops back round to `{}`", current));
+        }
+        let ruleset = rules
+            .get(current)
+            .ok_or_else(|| format!("no workflow called `{}`", current))?;
+        current = ruleset
+            .iter()
+            .find(|rule| rule.matches(item))
+            .map(|rule| rule.goto.as_str())
+            .ok_or_else(|| format!("no rule in `{}` matches", current))?;
     }
 }
 
 #[aoc(day19, part1)]
-pub fn part1(input: &Input) -> usize {
-    let (rules, items) = input;
+pub fn part1(input: &Input) -> i64 {
+    let (rules, _attributes, items) = input;
     let mut output = 0;
     for item in items {
-        if check_accepted(*item, rules) {
-            output += item.rating();
+        match check_accepted(item, rules) {
+            Ok(true) => output += item.rating(),
+            Ok(false) => (),
+            Err(e) => warn!(?item, "not accepted, {}", e),
         }
     }
     output
 }
 
-#[derive(Copy, Clone, Debug)]
-pub struct XmasInterval {
-    pub x: Option<Interval>,
-    pub m: Option<Interval>,
-    pub a: Option<Interval>,
-    pub s: Option<Interval>,
+/// An axis-aligned box of ratings, one side per attribute in `Attributes` order, inclusive at
+/// both ends.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct HyperRect {
+    pub sides: Vec<(i64, i64)>,
 }
 
-impl fmt::Display for XmasInterval {
+impl fmt::Display for HyperRect {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if self.x.is_some() && self.m.is_some() && self.a.is_some() && self.s.is_some() {
-            write!(
-                f,
-                "x: {}, m: {}, a: {}, s: {}",
-                self.x.unwrap(),
-                self.m.unwrap(),
-                self.a.unwrap(),
-                self.s.unwrap()
-            )
-        } else {
-            write!(f, "XMAS: Empty")
+        let sides: Vec<_> = self
+            .sides
+            .iter()
+            .map(|(lo, hi)| format!("[{}, {}]", lo, hi))
+            .collect();
+        write!(f, "{}", sides.join(" x "))
+    }
+}
+
+impl HyperRect {
+    pub fn new(sides: Vec<(i64, i64)>) -> Self {
+        Self { sides }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sides.iter().any(|(lo, hi)| lo > hi)
+    }
+
+    /// How many parts are in the box, or `None` if that's too many for a `u128`.
+    pub fn volume(&self) -> Option<u128> {
+        if self.is_empty() {
+            return Some(0);
         }
+        self.sides.iter().try_fold(1u128, |volume, &(lo, hi)| {
+            volume.checked_mul((hi as i128 - lo as i128 + 1) as u128)
+        })
     }
+
+    /// The same box with side `dim` cut down to `lo..=hi`.
+    pub fn restrict(&self, dim: usize, lo: i64, hi: i64) -> Self {
+        let mut sides = self.sides.clone();
+        sides[dim] = (sides[dim].0.max(lo), sides[dim].1.min(hi));
+        Self::new(sides)
+    }
+
+    pub fn intersection(&self, other: &HyperRect) -> Self {
+        let sides = self
+            .sides
+            .iter()
+            .zip(&other.sides)
+            .map(|(a, b)| (a.0.max(b.0), a.1.min(b.1)))
+            .collect();
+        Self::new(sides)
+    }
+
+    /// Everything in `self` but not `other`, as disjoint boxes. Cutting a hole out of the middle
+    /// leaves up to two boxes per side.
+    pub fn difference(&self, other: &HyperRect) -> Vec<HyperRect> {
+        if self.intersection(other).is_empty() {
+            return vec![self.clone()];
+        }
+        // Slice off whatever sticks out below and above `other` one side at a time, keeping the
+        // rest for the next side
+        let mut pieces = vec![];
+        let mut rest = self.clone();
+        for (dim, &(lo, hi)) in other.sides.iter().enumerate() {
+            let (own_lo, own_hi) = rest.sides[dim];
+            pieces.push(rest.restrict(dim, own_lo, lo - 1));
+            pieces.push(rest.restrict(dim, hi + 1, own_hi));
+            rest = rest.restrict(dim, lo, hi);
+        }
+        pieces.retain(|p| !p.is_empty());
+        pieces
+    }
+}
+
+/// A union of disjoint boxes.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RegionSet {
+    rects: Vec<HyperRect>,
 }
 
-impl Default for XmasInterval {
-    fn default() -> Self {
-        Self::new()
+impl From<HyperRect> for RegionSet {
+    fn from(rect: HyperRect) -> Self {
+        let mut region = Self::default();
+        region.insert(rect);
+        region
     }
 }
 
-impl XmasInterval {
-    pub fn new() -> Self {
-        Self {
-            x: Some(Interval::new(1, 4000)),
-            m: Some(Interval::new(1, 4000)),
-            a: Some(Interval::new(1, 4000)),
-            s: Some(Interval::new(1, 4000)),
+impl RegionSet {
+    pub fn rects(&self) -> &[HyperRect] {
+        &self.rects
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rects.is_empty()
+    }
+
+    pub fn volume(&self) -> Option<u128> {
+        self.rects
+            .iter()
+            .try_fold(0u128, |total, r| total.checked_add(r.volume()?))
+    }
+
+    /// Add `rect`, minus whatever's already covered so the boxes stay disjoint.
+    pub fn insert(&mut self, rect: HyperRect) {
+        let mut pieces = vec![rect];
+        for r in &self.rects {
+            pieces = pieces.iter().flat_map(|p| p.difference(r)).collect();
         }
+        self.rects
+            .extend(pieces.into_iter().filter(|p| !p.is_empty()));
     }
 
-    pub fn num_ways(&self) -> usize {
-        if self.x.is_some() && self.m.is_some() && self.a.is_some() && self.s.is_some() {
-            self.x.unwrap().len()
-                * self.m.unwrap().len()
-                * self.a.unwrap().len()
-                * self.s.unwrap().len()
-        } else {
-            0
+    pub fn union(&mut self, other: RegionSet) {
+        for rect in other.rects {
+            self.insert(rect);
         }
     }
 
-    pub fn apply_restriction(&mut self, field: char, restriction: Interval) {
-        match field {
-            'x' => {
-                if self.x.is_some() {
-                    let temp = self.x.unwrap().difference(restriction);
-                    if !temp.is_empty() {
-                        self.x = Some(temp[0]);
-                    } else {
-                        self.x = None;
-                    }
-                }
-            }
-            'm' => {
-                if self.m.is_some() {
-                    let temp = self.m.unwrap().difference(restriction);
-                    if !temp.is_empty() {
-                        self.m = Some(temp[0]);
-                    } else {
-                        self.m = None;
-                    }
-                }
+    pub fn intersection(&self, rect: &HyperRect) -> RegionSet {
+        let rects = self
+            .rects
+            .iter()
+            .map(|r| r.intersection(rect))
+            .filter(|r| !r.is_empty())
+            .collect();
+        Self { rects }
+    }
+
+    pub fn difference(&self, rect: &HyperRect) -> RegionSet {
+        let rects = self.rects.iter().flat_map(|r| r.difference(rect)).collect();
+        Self { rects }
+    }
+}
+
+impl Rule {
+    /// The parts in `bounds` that this rule sends on, or `None` if it takes them all.
+    pub fn matching(&self, attributes: &Attributes, bounds: &HyperRect) -> Option<HyperRect> {
+        let (Some(check), Some(value)) = (&self.check, self.value) else {
+            return None;
+        };
+        let (lo, hi) = self.rule_type.passing(value);
+        match attributes.index(check) {
+            Some(dim) => Some(bounds.restrict(dim, lo, hi)),
+            // Parts without the attribute never match
+            None => Some(bounds.restrict(0, 1, 0)),
+        }
+    }
+}
+
+/// Follow `start` through the workflows from `in`, splitting it up at each rule, and collect
+/// everything that reaches `A`. Like `check_accepted`, parts that loop, get lost or fall off
+/// the end of a workflow aren't accepted.
+pub fn accepted_region(
+    rules: &HashMap<String, Vec<Rule>>,
+    attributes: &Attributes,
+    start: HyperRect,
+) -> RegionSet {
+    let mut accepted = RegionSet::default();
+    // Each region carries the workflows it's been through
+    let mut todo = vec![("in", RegionSet::from(start.clone()), vec![])];
+    while let Some((name, mut region, mut path)) = todo.pop() {
+        match name {
+            "A" => {
+                accepted.union(region);
+                continue;
             }
-            'a' => {
-                if self.a.is_some() {
-                    let temp = self.a.unwrap().difference(restriction);
-                    if !temp.is_empty() {
-                        self.a = Some(temp[0]);
-                    } else {
-                        self.a = None;
-                    }
-                }
+            "R" => continue,
+            _ if path.contains(&name) => continue,
+            _ => path.push(name),
+        }
+        let Some(ruleset) = rules.get(name) else {
+            continue;
+        };
+        for rule in ruleset {
+            if region.is_empty() {
+                break;
             }
-            's' => {
-                if self.s.is_some() {
-                    let temp = self.s.unwrap().difference(restriction);
-                    if !temp.is_empty() {
-                        self.s = Some(temp[0]);
-                    } else {
-                        self.s = None;
-                    }
+            let taken = match rule.matching(attributes, &start) {
+                Some(rect) => {
+                    let taken = region.intersection(&rect);
+                    region = region.difference(&rect);
+                    taken
                 }
+                None => std::mem::take(&mut region),
+            };
+            if !taken.is_empty() {
+                todo.push((rule.goto.as_str(), taken, path.clone()));
             }
-            _ => panic!("invalid check"),
         }
     }
+    accepted
 }
 
-// Do to the construction of the problem we never have to worry about 1 Interval becoming two after
-// differencing out the middle.
-pub fn backpropagation(idx: usize, rule_tree: &Tree<(Rule, Option<bool>)>) -> XmasInterval {
-    let path = rule_tree.path_to_node(idx).unwrap();
-    let mut output = XmasInterval::new();
-    for idx in path {
-        let (rule, take_path) = &rule_tree.nodes[idx].value;
-        match rule.rule_type {
-            RuleType::LessThan => {
-                let restriction = if take_path.unwrap() {
-                    Interval::new(rule.value.unwrap() as i64, 4000)
-                } else {
-                    Interval::new(1, rule.value.unwrap() as i64 - 1)
-                };
-                output.apply_restriction(
-                    rule.check.clone().unwrap().chars().next().unwrap(),
-                    restriction,
-                );
+#[aoc(day19, part2)]
+// How many of the parts in `Attributes::bounds` are accepted?
+pub fn part2(input: &Input) -> u128 {
+    let (rules, attributes, _items) = input;
+    accepted_region(rules, attributes, attributes.bounds())
+        .volume()
+        .expect("the attributes' bounds were checked to fit in a u128")
+}
+
+/// What `analyze` found wrong with a set of workflows.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Analysis {
+    /// Groups of workflows that can send parts round in a loop, each sorted by name.
+    pub cycles: Vec<Vec<String>>,
+    /// Workflow and target of each rule that sends parts to a workflow that doesn't exist. A
+    /// missing `in` shows up with an empty workflow.
+    pub undefined: Vec<(String, String)>,
+    /// Workflows that nothing starting from `in` ever reaches.
+    pub unreachable: Vec<String>,
+    /// Workflow and index of each rule that can never fire, because the rules before it have
+    /// already taken everything it would match.
+    pub dead_rules: Vec<(String, usize)>,
+}
+
+impl Analysis {
+    pub fn is_clean(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl fmt::Display for Analysis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for cycle in &self.cycles {
+            writeln!(f, "cycle: {}", cycle.join(", "))?;
+        }
+        for (workflow, target) in &self.undefined {
+            writeln!(f, "undefined: `{}` sends to `{}`", workflow, target)?;
+        }
+        for workflow in &self.unreachable {
+            writeln!(f, "unreachable: `{}`", workflow)?;
+        }
+        for (workflow, i) in &self.dead_rules {
+            writeln!(f, "dead rule: `{}` rule {}", workflow, i + 1)?;
+        }
+        Ok(())
+    }
+}
+
+fn is_workflow(rules: &HashMap<String, Vec<Rule>>, name: &str) -> bool {
+    name != "A" && name != "R" && rules.contains_key(name)
+}
+
+/// Every workflow `from` can send parts to, directly or not.
+fn reachable<'a>(rules: &'a HashMap<String, Vec<Rule>>, from: &'a str) -> HashSet<&'a str> {
+    let mut seen = HashSet::new();
+    let mut todo = vec![from];
+    while let Some(name) = todo.pop() {
+        for rule in rules.get(name).into_iter().flatten() {
+            if is_workflow(rules, &rule.goto) && seen.insert(rule.goto.as_str()) {
+                todo.push(&rule.goto);
             }
-            RuleType::GreaterThan => {
-                let restriction = if take_path.unwrap() {
-                    Interval::new(1, rule.value.unwrap() as i64)
-                } else {
-                    Interval::new(rule.value.unwrap() as i64 + 1, 4000)
-                };
-                output.apply_restriction(
-                    rule.check.clone().unwrap().chars().next().unwrap(),
-                    restriction,
-                );
+        }
+    }
+    seen
+}
+
+/// Which rules in `ruleset` can never fire for parts within `attributes`' ranges.
+fn dead_rules(ruleset: &[Rule], attributes: &Attributes) -> Vec<usize> {
+    let bounds = attributes.bounds();
+    let mut left = RegionSet::from(bounds.clone());
+    let mut dead = vec![];
+    for (i, rule) in ruleset.iter().enumerate() {
+        let taken = match rule.matching(attributes, &bounds) {
+            Some(rect) => {
+                let taken = left.intersection(&rect);
+                left = left.difference(&rect);
+                taken
             }
-            RuleType::GoTo => (),
+            None => std::mem::take(&mut left),
+        };
+        if taken.is_empty() {
+            dead.push(i);
         }
     }
-    output
+    dead
 }
 
-#[aoc(day19, part2)]
-// Each attribute can be in [1..4000] inclusive. How many accepted combos?
-pub fn part2(input: &Input) -> usize {
-    let (rules, _items) = input;
-    let mut rule_tree = Tree::with_head((Rule::new(None, RuleType::GoTo, None, "in"), None));
-    let mut next_rules = vec![(0, String::from("in"))];
-    loop {
-        if next_rules.is_empty() {
-            break;
+pub fn analyze(rules: &HashMap<String, Vec<Rule>>, attributes: &Attributes) -> Analysis {
+    let mut names: Vec<&String> = rules.keys().collect();
+    names.sort();
+    let mut analysis = Analysis::default();
+
+    let reach: HashMap<&str, HashSet<&str>> = names
+        .iter()
+        .map(|n| (n.as_str(), reachable(rules, n)))
+        .collect();
+    let mut in_cycle = HashSet::new();
+    for name in &names {
+        if reach[name.as_str()].contains(name.as_str()) && !in_cycle.contains(name.as_str()) {
+            // Everything that can get back here is in the same loop
+            let cycle: Vec<String> = names
+                .iter()
+                .filter(|other| {
+                    reach[name.as_str()].contains(other.as_str())
+                        && reach[other.as_str()].contains(name.as_str())
+                })
+                .map(|other| other.to_string())
+                .collect();
+            in_cycle.extend(
+                cycle
+                    .iter()
+                    .map(|c| rules.get_key_value(c).unwrap().0.as_str()),
+            );
+            analysis.cycles.push(cycle);
         }
-        let mut new_next_rules = vec![];
-        for (_pid, current_rule) in &next_rules {
-            if current_rule == "A" || current_rule == "R" {
-                continue;
+    }
+
+    if !rules.contains_key("in") {
+        analysis.undefined.push((String::new(), "in".to_string()));
+    }
+    for name in &names {
+        for rule in &rules[name.as_str()] {
+            if rule.goto != "A" && rule.goto != "R" && !rules.contains_key(&rule.goto) {
+                analysis
+                    .undefined
+                    .push((name.to_string(), rule.goto.clone()));
             }
-            let mut pid = *_pid;
-            let rule_list = rules.get(current_rule).unwrap();
-            for rule in rule_list {
-                if rule.rule_type == RuleType::GoTo {
-                    let cid = rule_tree
-                        .add_child_to_node((rule.clone(), None), pid)
-                        .unwrap();
-                    new_next_rules.push((cid, rule.goto.clone()));
-                    pid = cid;
+        }
+    }
+
+    let from_in = reachable(rules, "in");
+    analysis.unreachable = names
+        .iter()
+        .filter(|n| n.as_str() != "in" && !from_in.contains(n.as_str()))
+        .map(|n| n.to_string())
+        .collect();
+
+    for name in &names {
+        for i in dead_rules(&rules[name.as_str()], attributes) {
+            analysis.dead_rules.push((name.to_string(), i));
+        }
+    }
+    analysis
+}
+
+/// An equivalent set of workflows, with dead rules dropped, rules that go the same way as the
+/// fallback folded into it, workflows that always send everything to one place inlined, and
+/// anything unreachable removed. Rules are only judged against `Attributes::bounds`, so parts
+/// rated outside that can be treated differently.
+pub fn simplify(
+    rules: &HashMap<String, Vec<Rule>>,
+    attributes: &Attributes,
+) -> HashMap<String, Vec<Rule>> {
+    let mut rules = rules.clone();
+    loop {
+        let before = rules.clone();
+        for ruleset in rules.values_mut() {
+            let dead = dead_rules(ruleset, attributes);
+            let mut i = 0;
+            ruleset.retain(|_| {
+                i += 1;
+                !dead.contains(&(i - 1))
+            });
+            // If a rule goes the same way as the one after it does for everything else, it
+            // makes no difference
+            while let [.., second, last] = &ruleset[..] {
+                if second.goto == last.goto && last.check.is_none() {
+                    ruleset.remove(ruleset.len() - 2);
                 } else {
-                    let cid1 = rule_tree
-                        .add_child_to_node((rule.clone(), Some(false)), pid)
-                        .unwrap();
-                    let cid2 = rule_tree
-                        .add_child_to_node((rule.clone(), Some(true)), pid)
-                        .unwrap();
-                    new_next_rules.push((cid2, rule.goto.clone()));
-                    pid = cid1; // If we continue along this path, then we didn't take the path and
-                                // pid should be this cid, not the next one
+                    break;
                 }
             }
         }
-        next_rules = new_next_rules;
-    }
-    // We have a tree built with '.path_to_node()' now... search for "A"s, and create Intervals and
-    // backpropagate.
-    let mut output = 0;
-    for idx in &rule_tree.leaves() {
-        let (rule, _take_path) = &rule_tree.nodes[*idx].value;
-        if rule.goto == "A" {
-            // Create interval and backpropagate
-            let interval = backpropagation(*idx, &rule_tree);
-            output += interval.num_ways();
+
+        // Workflows that are just a goto, apart from `in` which is where everything starts
+        let aliases: HashMap<String, String> = rules
+            .iter()
+            .filter(|(name, ruleset)| {
+                name.as_str() != "in"
+                    && matches!(&ruleset[..], [only] if only.check.is_none() && only.goto != **name)
+            })
+            .map(|(name, ruleset)| (name.clone(), ruleset[0].goto.clone()))
+            .collect();
+        for ruleset in rules.values_mut() {
+            for rule in ruleset.iter_mut() {
+                let mut seen = HashSet::new();
+                while let Some(next) = aliases.get(&rule.goto) {
+                    if !seen.insert(rule.goto.clone()) {
+                        // A loop of gotos, leave it be
+                        break;
+                    }
+                    rule.goto = next.clone();
+                }
+            }
+        }
+
+        let from_in: HashSet<String> = reachable(&rules, "in")
+            .into_iter()
+            .map(String::from)
+            .collect();
+        rules.retain(|name, _| name == "in" || from_in.contains(name.as_str()));
+        if rules == before {
+            return rules;
         }
     }
-    output
+}
+
+/// Workflows in the input's format, `in` first and then by name.
+pub fn format_workflows(rules: &HashMap<String, Vec<Rule>>) -> String {
+    let mut names: Vec<&String> = rules.keys().collect();
+    names.sort_by_key(|n| (n.as_str() != "in", n.as_str()));
+    names
+        .iter()
+        .map(|name| {
+            let ruleset: Vec<_> = rules[name.as_str()].iter().map(Rule::to_string).collect();
+            format!("{}{{{}}}\n", name, ruleset.join(","))
+        })
+        .collect()
+}
+
+pub struct Day19;
+
+impl Solution for Day19 {
+    type Input = Input;
+
+    const DAY: u32 = 19;
+
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        load_input(input)
+    }
+
+    fn part1(input: &Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Input) -> Answer {
+        part2(input).into()
+    }
 }
 
 #[cfg(test)]
@@ -351,14 +749,173 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2023/19a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 19114);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2023/19a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 167409079868000);
     }
+
+    #[test]
+    fn test_attributes() {
+        let input = load_input(
+            "in{cost>=10:A,cost==5:A,weight<=2:hi,R}\nhi{weight<0:R,A}\n\n{cost=5,weight=100}\n{weight=-1,cost=5}\n{cost=7,weight=2}\n{cost=10,weight=3}",
+        )
+        .unwrap();
+        let (rules, attributes, items) = &input;
+        assert_eq!(attributes.names(), ["cost", "weight"]);
+        let accepted: Vec<_> = items.iter().map(|i| check_accepted(i, rules)).collect();
+        assert_eq!(accepted, vec![Ok(true); 4]);
+        assert_eq!(part1(&input), 105 + 4 + 9 + 13);
+        assert_eq!(
+            check_accepted(&Item::new(&[("cost", 7), ("weight", -3)]), rules),
+            Ok(false)
+        );
+        // A part with no `cost` doesn't match any rule on it
+        assert_eq!(
+            check_accepted(&Item::new(&[("weight", 3)]), rules),
+            Ok(false)
+        );
+
+        // Costs 10..=20 and 5 pass with any weight, the other 8 costs only with weights 0..=2
+        let attributes = attributes
+            .clone()
+            .with_range("cost", 1, 20)
+            .and_then(|a| a.with_range("weight", -5, 5))
+            .unwrap();
+        let region = accepted_region(rules, &attributes, attributes.bounds());
+        assert_eq!(region.volume(), Some(12 * 11 + 8 * 3));
+
+        assert!(attributes.clone().with_range("nope", 1, 2).is_err());
+        assert!(attributes.clone().with_range("cost", 2, 1).is_err());
+
+        // Six attributes at the default range don't fit in a u64
+        let input = load_input("in{a>0:A,R}\n\n{a=1,b=1,c=1,d=1,e=1,f=1}").unwrap();
+        assert_eq!(part2(&input), 4000u128.pow(6));
+        // Ten still fit in a u128, eleven don't
+        let ten = "{a=1,b=1,c=1,d=1,e=1,f=1,g=1,h=1,i=1,j=1}";
+        let input = load_input(&format!("in{{a>0:A,R}}\n\n{}", ten)).unwrap();
+        assert_eq!(part2(&input), 4000u128.pow(10));
+        let err =
+            load_input(&format!("in{{a>0:A,R}}\n\n{}", ten.replace('}', ",k=1}"))).unwrap_err();
+        assert_eq!((err.line, err.column), (3, 42));
+        let big = HyperRect::new(vec![(i64::MIN, i64::MAX); 3]);
+        assert_eq!(big.volume(), None);
+        assert_eq!(RegionSet::from(big).volume(), None);
+        let (_, attributes, _) = load_input("in{a>0:A,b>0:A,R}").unwrap();
+        assert!(attributes.with_range("a", i64::MIN, i64::MAX).is_ok());
+        let input = load_input("in{x<-9223372036854775808:A,x>9223372036854775807:A,R}").unwrap();
+        assert_eq!(part2(&input), 0);
+
+        assert!(load_input("in{x<1:A,R}\n\n{x=1,m=2}\n{m=2}").is_err());
+        assert!(load_input("in{q<1:A,R}\n\n{x=1}").is_err());
+        assert!(load_input("in{x=1:A,R}").is_err());
+        let (_, attributes, _) = load_input("in{x<1:y,R}\ny{m>1:A,x<0:R,A}").unwrap();
+        assert_eq!(attributes.names(), ["x", "m"]);
+    }
+
+    #[test]
+    fn test_analyze() {
+        let input = read_to_string("input/2023/19a.txt").unwrap();
+        let input = load_input(&input).unwrap();
+        let (rules, attributes, items) = &input;
+        let analysis = analyze(rules, attributes);
+        assert!(analysis.is_clean(), "{}", analysis);
+
+        let simple = simplify(rules, attributes);
+        assert_eq!(simple["in"], rules["in"]);
+        // `lnx` always accepts and `gd` always rejects, so they're gone
+        assert!(!simple.contains_key("lnx") && !simple.contains_key("gd"));
+        // `qs` was left always accepting too
+        assert_eq!(
+            format_workflows(&simple),
+            "in{s<1351:px,qqz}\n\
+             crn{x>2662:A,R}\n\
+             hdj{m>838:A,pv}\n\
+             pv{a>1716:R,A}\n\
+             px{a<2006:qkq,m>2090:A,rfg}\n\
+             qkq{x<1416:A,crn}\n\
+             qqz{s>2770:A,m<1801:hdj,R}\n\
+             rfg{s<537:R,x>2440:R,A}\n"
+        );
+        for item in items {
+            assert_eq!(check_accepted(item, &simple), check_accepted(item, rules));
+        }
+        let region = accepted_region(&simple, attributes, attributes.bounds());
+        assert_eq!(region.volume(), Some(167409079868000));
+
+        // Nothing in range is above 4000, but a part outside it can still get rejected
+        let (rules, attributes, items) = load_input("in{x>4000:R,A}\n\n{x=5000}").unwrap();
+        let simple = simplify(&rules, &attributes);
+        assert_eq!(format_workflows(&simple), "in{A}\n");
+        assert_eq!(check_accepted(&items[0], &rules), Ok(false));
+        assert_eq!(check_accepted(&items[0], &simple), Ok(true));
+
+        let (rules, attributes, _) =
+            load_input("in{x<10:a,x<5:R,y>4000:R,b}\na{b}\nb{x>100:a,nope}\nc{A}\nd{x==0:A,R}")
+                .unwrap();
+        let attributes = attributes.with_range("x", 1, 4000).unwrap();
+        let analysis = analyze(&rules, &attributes);
+        assert_eq!(
+            analysis.cycles,
+            vec![vec!["a".to_string(), "b".to_string()]]
+        );
+        assert_eq!(
+            analysis.undefined,
+            vec![("b".to_string(), "nope".to_string())]
+        );
+        assert_eq!(analysis.unreachable, ["c", "d"]);
+        assert_eq!(
+            analysis.dead_rules,
+            vec![
+                ("d".to_string(), 0),
+                ("in".to_string(), 1),
+                ("in".to_string(), 2)
+            ]
+        );
+
+        // `a` and `b` send each other parts with x > 100 forever, the rest get lost
+        let item = |x| Item::new(&[("x", x), ("y", 1)]);
+        assert!(check_accepted(&item(200), &rules).is_err());
+        assert!(check_accepted(&item(50), &rules).is_err());
+        assert_eq!(
+            accepted_region(&rules, &attributes, attributes.bounds()).volume(),
+            Some(0)
+        );
+        let (rules, _, _) = load_input("in{x<10:A}").unwrap();
+        assert!(check_accepted(&item(50), &rules).is_err());
+        assert_eq!(check_accepted(&item(5), &rules), Ok(true));
+    }
+
+    #[test]
+    fn test_regions() {
+        let outer = HyperRect::new(vec![(1, 10), (1, 10)]);
+        let hole = HyperRect::new(vec![(4, 6), (4, 6)]);
+        let ring = outer.difference(&hole);
+        assert_eq!(ring.len(), 4);
+        assert_eq!(
+            ring.iter().filter_map(HyperRect::volume).sum::<u128>(),
+            100 - 9
+        );
+        assert!(ring.iter().all(|r| r.intersection(&hole).is_empty()));
+        assert_eq!(outer.intersection(&hole), hole);
+        assert!(hole.difference(&outer).is_empty());
+        assert!(HyperRect::new(vec![(1, 10), (5, 4)]).is_empty());
+
+        let mut region = RegionSet::from(hole.clone());
+        region.insert(HyperRect::new(vec![(5, 8), (5, 8)]));
+        assert_eq!(region.volume(), Some(9 + 16 - 4));
+        assert_eq!(
+            region
+                .intersection(&HyperRect::new(vec![(6, 6), (1, 10)]))
+                .volume(),
+            Some(5)
+        );
+        assert_eq!(region.difference(&hole).volume(), Some(12));
+        assert!(region.difference(&outer).is_empty());
+    }
 }
@@ -1,11 +1,12 @@
+use crate::parse::{self, ParseError};
+use crate::solution::{Answer, Solution};
 type Input = Vec<Vec<(usize, usize, usize)>>;
 
 #[aoc_generator(day2)]
-pub fn load_input(input: &str) -> Input {
+pub fn load_input(input: &str) -> Result<Input, ParseError> {
     let mut games = vec![];
-    for line in input.lines() {
-        let mut line_iter = line.split(':');
-        let obs = line_iter.nth(1).unwrap();
+    for line in parse::lines(2, input) {
+        let (_, obs) = line.split_once(line.text, ":")?;
         let gobs_iter = obs.split(';');
 
         let mut observations = vec![];
@@ -16,14 +17,13 @@ pub fn load_input(input: &str) -> Input {
             let mut green = 0;
             let mut blue = 0;
             for v in value_split {
-                let value = v.split(' ').next().unwrap().parse::<usize>().unwrap();
-                let color = v.split(' ').nth(1).unwrap();
-                if color == "red" {
-                    red = value;
-                } else if color == "green" {
-                    green = value;
-                } else if color == "blue" {
-                    blue = value;
+                let (value, color) = line.split_once(v, " ")?;
+                let value = line.parse::<usize>(value, "a number")?;
+                match color {
+                    "red" => red = value,
+                    "green" => green = value,
+                    "blue" => blue = value,
+                    _ => return Err(line.error(color, "`red`, `green` or `blue`")),
                 }
             }
             observations.push((red, green, blue));
@@ -31,7 +31,7 @@ pub fn load_input(input: &str) -> Input {
 
         games.push(observations);
     }
-    games
+    Ok(games)
 }
 
 #[aoc(day2, part1)]
@@ -89,6 +89,26 @@ pub fn part2(input: &Input) -> usize {
     output
 }
 
+pub struct Day2;
+
+impl Solution for Day2 {
+    type Input = Input;
+
+    const DAY: u32 = 2;
+
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        load_input(input)
+    }
+
+    fn part1(input: &Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Input) -> Answer {
+        part2(input).into()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -97,14 +117,14 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2023/02a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 8);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2023/02a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 2286);
     }
 }
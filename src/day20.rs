@@ -1,11 +1,10 @@
-use std::collections::HashMap;
+use crate::parse::{self, ParseError};
+use crate::solution::{Answer, Solution};
+use std::collections::{HashMap, VecDeque};
 use std::fmt;
+use tracing::debug;
 
-pub type Input = (
-    HashMap<String, FlipFlop>,
-    HashMap<String, Conjunction>,
-    Vec<String>,
-);
+pub type Input = Circuit;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Pulse {
@@ -15,9 +14,9 @@ pub enum Pulse {
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Signal {
-    src: String,
-    dst: String,
-    pulse: Pulse,
+    pub src: String,
+    pub dst: String,
+    pub pulse: Pulse,
 }
 
 impl Signal {
@@ -36,246 +35,738 @@ impl fmt::Display for Signal {
     }
 }
 
-#[derive(Clone, Debug)]
+/// Anything that can sit in a `Circuit`. Modules are told about their inputs by number, and
+/// whatever they send goes to all of their outputs.
+pub trait Module: fmt::Debug {
+    /// What sort of module this is, e.g. `flip-flop`.
+    fn kind(&self) -> &'static str;
+
+    /// Called once for each module that sends to this one, before any pulses arrive.
+    fn connect(&mut self, _input: usize) {}
+
+    /// Handle `pulse` from module `from`, returning the pulse to send on, if any.
+    fn receive(&mut self, from: usize, pulse: Pulse) -> Option<Pulse>;
+
+    /// Whatever the module remembers between pulses, as bits.
+    fn state(&self) -> Vec<bool> {
+        vec![]
+    }
+
+    fn clone_box(&self) -> Box<dyn Module>;
+}
+
+impl Clone for Box<dyn Module> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Broadcaster;
+
+impl Module for Broadcaster {
+    fn kind(&self) -> &'static str {
+        "broadcaster"
+    }
+
+    fn receive(&mut self, _from: usize, pulse: Pulse) -> Option<Pulse> {
+        Some(pulse)
+    }
+
+    fn clone_box(&self) -> Box<dyn Module> {
+        Box::new(self.clone())
+    }
+}
+
+#[derive(Clone, Debug, Default)]
 pub struct FlipFlop {
-    pub state: bool,
-    pub output: Vec<String>,
+    pub on: bool,
 }
 
-impl FlipFlop {
-    pub fn new(output: &[String]) -> Self {
-        Self {
-            state: false,
-            output: output.to_vec(),
-        }
+impl Module for FlipFlop {
+    fn kind(&self) -> &'static str {
+        "flip-flop"
     }
 
-    pub fn send(&mut self, pulse: Pulse) -> Option<Pulse> {
+    fn receive(&mut self, _from: usize, pulse: Pulse) -> Option<Pulse> {
         match pulse {
             Pulse::Lo => {
-                self.state ^= true;
-                if self.state {
-                    Some(Pulse::Hi)
-                } else {
-                    Some(Pulse::Lo)
-                }
+                self.on = !self.on;
+                Some(if self.on { Pulse::Hi } else { Pulse::Lo })
             }
             Pulse::Hi => None,
         }
     }
+
+    fn state(&self) -> Vec<bool> {
+        vec![self.on]
+    }
+
+    fn clone_box(&self) -> Box<dyn Module> {
+        Box::new(self.clone())
+    }
 }
 
-#[derive(Clone, Debug)]
+/// Remembers the last pulse from each input, and sends low only once they're all high.
+#[derive(Clone, Debug, Default)]
 pub struct Conjunction {
-    pub state: Vec<(String, Pulse)>,
-    pub output: Vec<String>,
+    pub memory: Vec<(usize, Pulse)>,
 }
 
-impl Conjunction {
-    pub fn new(output: &[String]) -> Self {
-        Self {
-            state: vec![],
-            output: output.to_vec(),
+impl Module for Conjunction {
+    fn kind(&self) -> &'static str {
+        "conjunction"
+    }
+
+    fn connect(&mut self, input: usize) {
+        if !self.memory.iter().any(|(id, _)| *id == input) {
+            self.memory.push((input, Pulse::Lo));
+        }
+    }
+
+    fn receive(&mut self, from: usize, pulse: Pulse) -> Option<Pulse> {
+        if let Some(m) = self.memory.iter_mut().find(|(id, _)| *id == from) {
+            m.1 = pulse;
+        }
+        if self.memory.iter().all(|(_, p)| *p == Pulse::Hi) {
+            Some(Pulse::Lo)
+        } else {
+            Some(Pulse::Hi)
+        }
+    }
+
+    /// Which inputs last sent high, in the order they were connected.
+    fn state(&self) -> Vec<bool> {
+        self.memory.iter().map(|(_, p)| *p == Pulse::Hi).collect()
+    }
+
+    fn clone_box(&self) -> Box<dyn Module> {
+        Box::new(self.clone())
+    }
+}
+
+/// A module that only listens, like `rx`. Anything that's sent to but never declared is one.
+#[derive(Clone, Debug, Default)]
+pub struct Output {
+    pub lows: usize,
+    pub highs: usize,
+}
+
+impl Module for Output {
+    fn kind(&self) -> &'static str {
+        "output"
+    }
+
+    fn receive(&mut self, _from: usize, pulse: Pulse) -> Option<Pulse> {
+        match pulse {
+            Pulse::Lo => self.lows += 1,
+            Pulse::Hi => self.highs += 1,
         }
+        None
     }
 
-    pub fn send(&mut self, input: &str, pulse: Pulse) -> Pulse {
-        for i in 0..self.state.len() {
-            if self.state[i].0.as_str() == input {
-                self.state[i].1 = pulse;
-                break;
+    fn clone_box(&self) -> Box<dyn Module> {
+        Box::new(self.clone())
+    }
+}
+
+/// Where button presses come from. It isn't a module, it only ever sends to `broadcaster`.
+pub const BUTTON: &str = "button";
+const BUTTON_ID: usize = usize::MAX;
+
+/// Why `Circuit::new` couldn't wire up its modules.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CircuitError {
+    /// The module at this index has the same name as one before it.
+    Duplicate(usize),
+    /// None of the modules is called `broadcaster`.
+    NoBroadcaster,
+}
+
+impl fmt::Display for CircuitError {
+    // Says what was expected, like a `ParseError`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CircuitError::Duplicate(_) => write!(f, "a module that isn't already declared"),
+            CircuitError::NoBroadcaster => write!(f, "a `broadcaster` module"),
+        }
+    }
+}
+
+/// A set of wired-up modules. They're numbered breadth first from `broadcaster`, so each one
+/// comes after the first module that can reach it.
+#[derive(Clone, Debug)]
+pub struct Circuit {
+    names: Vec<String>,
+    modules: Vec<Box<dyn Module>>,
+    outputs: Vec<Vec<usize>>,
+    queue: VecDeque<(usize, usize, Pulse)>,
+}
+
+impl Circuit {
+    /// Wire up `modules`, each given with the names it sends to.
+    pub fn new(modules: Vec<(String, Box<dyn Module>, Vec<String>)>) -> Result<Self, CircuitError> {
+        let mut declared: HashMap<String, (Box<dyn Module>, Vec<String>)> = HashMap::new();
+        let mut order = vec![];
+        for (i, (name, module, outputs)) in modules.into_iter().enumerate() {
+            if declared.contains_key(&name) {
+                return Err(CircuitError::Duplicate(i));
             }
+            order.push(name.clone());
+            declared.insert(name, (module, outputs));
+        }
+        if !declared.contains_key("broadcaster") {
+            return Err(CircuitError::NoBroadcaster);
         }
 
-        let values: Vec<_> = self.state.iter().map(|(_id, pulse)| pulse).collect();
-        let mut all_high = true;
-        for v in values {
-            if *v != Pulse::Hi {
-                all_high = false;
-                break;
+        // Number everything reachable from the broadcaster first, then whatever's left over
+        let mut names: Vec<String> = vec![];
+        let mut ids: HashMap<String, usize> = HashMap::new();
+        for root in std::iter::once("broadcaster").chain(order.iter().map(String::as_str)) {
+            let mut todo = VecDeque::from([root.to_string()]);
+            while let Some(name) = todo.pop_front() {
+                if ids.contains_key(&name) {
+                    continue;
+                }
+                ids.insert(name.clone(), names.len());
+                if let Some((_, outputs)) = declared.get(&name) {
+                    todo.extend(outputs.iter().cloned());
+                }
+                names.push(name);
+            }
+        }
+
+        let mut modules: Vec<Box<dyn Module>> = vec![];
+        let mut outputs = vec![];
+        for name in &names {
+            let (module, outs) = declared
+                .remove(name)
+                .unwrap_or_else(|| (Box::new(Output::default()), vec![]));
+            modules.push(module);
+            outputs.push(outs.iter().map(|o| ids[o]).collect::<Vec<_>>());
+        }
+        for (src, outs) in outputs.iter().enumerate() {
+            for &dst in outs {
+                modules[dst].connect(src);
             }
         }
+        Ok(Self {
+            names,
+            modules,
+            outputs,
+            queue: VecDeque::new(),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.modules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.modules.is_empty()
+    }
+
+    pub fn id(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|n| n == name)
+    }
 
-        if all_high {
-            Pulse::Lo
+    pub fn name(&self, id: usize) -> &str {
+        if id == BUTTON_ID {
+            BUTTON
         } else {
-            Pulse::Hi
+            &self.names[id]
         }
     }
-}
 
-#[aoc_generator(day20)]
-pub fn load_input(input: &str) -> Input {
-    let mut ffs: HashMap<String, FlipFlop> = HashMap::new();
-    let mut cons: HashMap<String, Conjunction> = HashMap::new();
-    let mut starts: Vec<String> = vec![];
-    for line in input.lines() {
-        let io: Vec<_> = line.split(" -> ").collect();
-
-        // First scan the name of the entity
-        let entity = io[0].to_string();
-
-        // Now get the list of destination entities
-        let dsts: Vec<_> = io[1].split(", ").collect();
-        match entity.chars().next().unwrap() {
-            '%' => {
-                let id: String = entity.chars().skip(1).fold(String::new(), |mut acc, c| {
-                    acc.push(c);
-                    acc
-                });
-                let ff =
-                    FlipFlop::new(&dsts.into_iter().map(|s| s.to_string()).collect::<Vec<_>>());
-                ffs.insert(id, ff);
+    pub fn module(&self, id: usize) -> &dyn Module {
+        self.modules[id].as_ref()
+    }
+
+    pub fn outputs(&self, id: usize) -> &[usize] {
+        &self.outputs[id]
+    }
+
+    /// Everything that sends to `id`, lowest number first.
+    pub fn inputs(&self, id: usize) -> Vec<usize> {
+        (0..self.len())
+            .filter(|&src| self.outputs[src].contains(&id))
+            .collect()
+    }
+
+    fn signal(&self, (src, dst, pulse): (usize, usize, Pulse)) -> Signal {
+        Signal::new(self.name(src), self.name(dst), pulse)
+    }
+
+    /// Queue a low pulse from the button to the broadcaster, without delivering anything.
+    pub fn push_button(&mut self) {
+        // `new` made sure there's a broadcaster, and it's always first
+        self.queue.push_back((BUTTON_ID, 0, Pulse::Lo));
+    }
+
+    fn deliver(&mut self) -> Option<(usize, usize, Pulse)> {
+        let (src, dst, pulse) = self.queue.pop_front()?;
+        if let Some(out) = self.modules[dst].receive(src, pulse) {
+            for &next in &self.outputs[dst] {
+                self.queue.push_back((dst, next, out));
             }
-            '&' => {
-                // TODO: Need to set up the inputs for these guys
-                let id: String = entity.chars().skip(1).fold(String::new(), |mut acc, c| {
-                    acc.push(c);
-                    acc
-                });
-                let con =
-                    Conjunction::new(&dsts.into_iter().map(|s| s.to_string()).collect::<Vec<_>>());
-                cons.insert(id, con);
+        }
+        Some((src, dst, pulse))
+    }
+
+    /// Deliver the next queued pulse, queueing whatever it sets off. `None` once everything has
+    /// settled down.
+    pub fn step(&mut self) -> Option<Signal> {
+        self.deliver().map(|sent| self.signal(sent))
+    }
+
+    /// Push the button and run until everything settles, adding each pulse to `trace` as it's
+    /// delivered. Returns how many low and high pulses there were.
+    pub fn press(&mut self, mut trace: Option<&mut Vec<Signal>>) -> (usize, usize) {
+        self.push_button();
+        let (mut lo, mut hi) = (0, 0);
+        while let Some(sent) = self.deliver() {
+            match sent.2 {
+                Pulse::Lo => lo += 1,
+                Pulse::Hi => hi += 1,
             }
-            'b' => {
-                starts = dsts.into_iter().map(|s| s.to_string()).collect();
+            if let Some(trace) = trace.as_deref_mut() {
+                trace.push(self.signal(sent));
             }
-            _ => panic!("not possible"),
         }
+        (lo, hi)
     }
+}
 
-    // Now we need to set up the inputs for the Conjunctions somehow
-    let cons_copy = cons.clone();
-    for (conid, cval) in cons.iter_mut() {
-        let ffins: Vec<String> = ffs
-            .iter()
-            .filter(|(_k, v)| v.output.contains(conid))
-            .map(|(k, _v)| k.to_string())
-            .collect();
-        let conins: Vec<String> = cons_copy
-            .iter()
-            .filter(|(_k, v)| v.output.contains(conid))
-            .map(|(k, _v)| k.to_string())
-            .collect();
-        for entity_id in ffins.iter().chain(conins.iter()) {
-            cval.state.push((entity_id.to_string(), Pulse::Lo));
+impl Circuit {
+    /// Graphviz DOT source for the circuit, with a shape for each kind of module. With
+    /// `colored`, modules are filled in by how many of their state bits are set.
+    pub fn to_dot(&self, colored: bool) -> String {
+        let mut dot = String::from("digraph circuit {\n    rankdir=LR;\n");
+        dot += &format!("    \"{}\" [shape=plaintext];\n", BUTTON);
+        for (id, module) in self.modules.iter().enumerate() {
+            let shape = match module.kind() {
+                "broadcaster" => "doubleoctagon",
+                "flip-flop" => "box",
+                "conjunction" => "invtrapezoid",
+                "output" => "doublecircle",
+                _ => "ellipse",
+            };
+            dot += &format!("    \"{}\" [shape={}", self.names[id], shape);
+            let bits = module.state();
+            if colored && !bits.is_empty() {
+                let set = bits.iter().filter(|b| **b).count();
+                let color = if set == bits.len() {
+                    "#ff8080"
+                } else if set > 0 {
+                    "#ffd080"
+                } else {
+                    "#d0d0d0"
+                };
+                dot += &format!(", style=filled, fillcolor=\"{}\"", color);
+                if bits.len() > 1 {
+                    dot += &format!(", label=\"{}\\n{}/{}\"", self.names[id], set, bits.len());
+                }
+            }
+            dot += "];\n";
         }
-        if starts.contains(conid) {
-            cval.state.push((String::from("broadcast"), Pulse::Lo));
+        dot += &format!("    \"{}\" -> \"{}\";\n", BUTTON, self.names[0]);
+        for (src, outs) in self.outputs.iter().enumerate() {
+            for &dst in outs {
+                dot += &format!("    \"{}\" -> \"{}\";\n", self.names[src], self.names[dst]);
+            }
         }
+        dot + "}\n"
     }
-    //println!("ffs: {:?}", ffs);
-    //println!("cons: {:?}", cons);
-    (ffs, cons, starts)
 }
 
-pub fn push_button(
-    flip_flops: &mut HashMap<String, FlipFlop>,
-    conjunctions: &mut HashMap<String, Conjunction>,
-    starts: &[String],
-    btn_presses: usize,
-    part2: bool,
-) -> (usize, usize) {
-    let mut lo_cntr = 1;
-    let mut hi_cntr = 0;
-    let mut next_pulses: Vec<Signal> = vec![];
-    // Push the button and broadcast to `starts`
-    for (ffid, _ff) in flip_flops.iter_mut() {
-        if starts.contains(ffid) {
-            next_pulses.push(Signal::new("broadcast", ffid, Pulse::Lo));
-            lo_cntr += 1;
+/// Every module's state bits packed into words, in module order.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Snapshot(Vec<u64>);
+
+impl Snapshot {
+    pub fn pack(bits: impl IntoIterator<Item = bool>) -> Self {
+        let mut words = vec![];
+        for (i, bit) in bits.into_iter().enumerate() {
+            if i % 64 == 0 {
+                words.push(0);
+            }
+            if bit {
+                words[i / 64] |= 1 << (i % 64);
+            }
         }
+        Self(words)
     }
-    for (conid, _con) in conjunctions.iter_mut() {
-        if starts.contains(conid) {
-            next_pulses.push(Signal::new("broadcast", conid, Pulse::Lo));
-            lo_cntr += 1;
-        }
+}
+
+impl Circuit {
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot::pack(self.modules.iter().flat_map(|m| m.state()))
     }
 
-    loop {
-        if next_pulses.is_empty() {
-            break;
+    /// The state of just the modules in `ids`, which should be in order.
+    pub fn snapshot_of(&self, ids: &[usize]) -> Snapshot {
+        Snapshot::pack(ids.iter().flat_map(|&id| self.modules[id].state()))
+    }
+
+    /// The strongly connected components, each in module order, with every component after the
+    /// ones that feed it.
+    pub fn subcircuits(&self) -> Vec<Vec<usize>> {
+        // Tarjan's algorithm, which finds them sinks first
+        struct Tarjan<'a> {
+            outputs: &'a [Vec<usize>],
+            next: usize,
+            index: Vec<Option<usize>>,
+            low: Vec<usize>,
+            stack: Vec<usize>,
+            on_stack: Vec<bool>,
+            found: Vec<Vec<usize>>,
         }
-        //println!("next_pulses: {:?}", next_pulses);
-        let mut new_next_pulses: Vec<Signal> = vec![];
-        for sig in &next_pulses {
-            if part2 {
-                if sig.dst == "rx" && sig.pulse == Pulse::Lo {
-                    return (0, 0);
-                }
-                if sig.dst == "xn" && sig.pulse == Pulse::Lo {
-                    println!("btn: {}", btn_presses);
-                    println!("sig: {}", sig);
-                }
-            }
-            //println!("{}", sig);
-            let target = sig.dst.clone();
-            let from = sig.src.clone();
-            let pulse = sig.pulse;
-            if let Some(ff) = flip_flops.get_mut(&target) {
-                let outpulse = ff.send(pulse);
-                if let Some(npulse) = outpulse {
-                    for next_target in &ff.output {
-                        new_next_pulses.push(Signal::new(&target, next_target, npulse));
-                        if npulse == Pulse::Lo {
-                            lo_cntr += 1;
-                        } else {
-                            hi_cntr += 1;
+
+        impl Tarjan<'_> {
+            fn visit(&mut self, v: usize) {
+                let index = self.next;
+                self.next += 1;
+                self.index[v] = Some(index);
+                self.low[v] = index;
+                self.stack.push(v);
+                self.on_stack[v] = true;
+                for &w in &self.outputs[v] {
+                    match self.index[w] {
+                        None => {
+                            self.visit(w);
+                            self.low[v] = self.low[v].min(self.low[w]);
                         }
+                        Some(i) if self.on_stack[w] => self.low[v] = self.low[v].min(i),
+                        Some(_) => (),
                     }
                 }
-            } else if let Some(con) = conjunctions.get_mut(&target) {
-                let outpulse = con.send(&from, pulse);
-                for next_target in &con.output {
-                    new_next_pulses.push(Signal::new(&target, next_target, outpulse));
-                    if outpulse == Pulse::Lo {
-                        lo_cntr += 1;
-                    } else {
-                        hi_cntr += 1;
+                if self.low[v] == index {
+                    let at = self.stack.iter().position(|&w| w == v).unwrap();
+                    let mut component = self.stack.split_off(at);
+                    for &w in &component {
+                        self.on_stack[w] = false;
                     }
+                    component.sort();
+                    self.found.push(component);
+                }
+            }
+        }
+
+        let n = self.len();
+        let mut tarjan = Tarjan {
+            outputs: &self.outputs,
+            next: 0,
+            index: vec![None; n],
+            low: vec![0; n],
+            stack: vec![],
+            on_stack: vec![false; n],
+            found: vec![],
+        };
+        for v in 0..n {
+            if tarjan.index[v].is_none() {
+                tarjan.visit(v);
+            }
+        }
+        let mut found = tarjan.found;
+        found.reverse();
+        found
+    }
+
+    /// `ids` and everything that can reach them, in module order. The state of these only
+    /// depends on each other, so they cycle as a whole.
+    pub fn upstream(&self, ids: &[usize]) -> Vec<usize> {
+        let mut seen = vec![false; self.len()];
+        let mut todo = ids.to_vec();
+        while let Some(id) = todo.pop() {
+            if !seen[id] {
+                seen[id] = true;
+                todo.extend(self.inputs(id));
+            }
+        }
+        (0..self.len()).filter(|&id| seen[id]).collect()
+    }
+}
+
+/// The state after `start` presses comes round again every `period` presses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cycle {
+    pub start: usize,
+    pub period: usize,
+}
+
+/// Press the button until the state of each group of modules repeats, or `max_presses` runs out.
+fn find_cycles(input: &Input, groups: &[Vec<usize>], max_presses: usize) -> Vec<Option<Cycle>> {
+    let mut circuit = input.clone();
+    let mut seen: Vec<HashMap<Snapshot, usize>> = vec![HashMap::new(); groups.len()];
+    let mut cycles = vec![None; groups.len()];
+    for presses in 0..=max_presses {
+        for (i, group) in groups.iter().enumerate() {
+            if cycles[i].is_none() {
+                let snapshot = circuit.snapshot_of(group);
+                if let Some(&start) = seen[i].get(&snapshot) {
+                    cycles[i] = Some(Cycle {
+                        start,
+                        period: presses - start,
+                    });
+                } else {
+                    seen[i].insert(snapshot, presses);
                 }
             }
         }
-        next_pulses = new_next_pulses;
+        if cycles.iter().all(Option::is_some) {
+            break;
+        }
+        circuit.press(None);
+    }
+    cycles
+}
+
+/// When the whole circuit first gets back to a state it's been in before.
+pub fn find_cycle(input: &Input, max_presses: usize) -> Option<Cycle> {
+    let everything: Vec<usize> = (0..input.len()).collect();
+    find_cycles(input, &[everything], max_presses)[0]
+}
+
+/// The cycle of each strongly connected subcircuit, along with everything upstream of it.
+pub fn subcircuit_cycles(input: &Input, max_presses: usize) -> Vec<(Vec<String>, Option<Cycle>)> {
+    let subcircuits = input.subcircuits();
+    let groups: Vec<_> = subcircuits.iter().map(|s| input.upstream(s)).collect();
+    let cycles = find_cycles(input, &groups, max_presses);
+    subcircuits
+        .iter()
+        .zip(cycles)
+        .map(|(s, cycle)| {
+            let names = s.iter().map(|&id| input.name(id).to_string()).collect();
+            (names, cycle)
+        })
+        .collect()
+}
+
+/// How many low and high pulses `presses` presses send. Once the circuit's state repeats, the
+/// rest are worked out from the cycle rather than simulated.
+pub fn pulses_after(input: &Input, presses: usize) -> (usize, usize) {
+    let mut circuit = input.clone();
+    let mut seen: HashMap<Snapshot, usize> = HashMap::new();
+    // Running totals after each press
+    let mut totals = vec![(0, 0)];
+    for done in 0..presses {
+        let snapshot = circuit.snapshot();
+        if let Some(&start) = seen.get(&snapshot) {
+            let period = done - start;
+            let (cycles, rest) = ((presses - start) / period, (presses - start) % period);
+            let diff = |a: usize, b: usize| (totals[a].0 - totals[b].0, totals[a].1 - totals[b].1);
+            let (cycle_lo, cycle_hi) = diff(done, start);
+            let (rest_lo, rest_hi) = diff(start + rest, start);
+            debug!(start, period, "state repeated");
+            return (
+                totals[start].0 + cycles * cycle_lo + rest_lo,
+                totals[start].1 + cycles * cycle_hi + rest_hi,
+            );
+        }
+        seen.insert(snapshot, done);
+        let (lo, hi) = circuit.press(None);
+        let (total_lo, total_hi) = totals[done];
+        totals.push((total_lo + lo, total_hi + hi));
     }
+    totals[presses]
+}
+
+/// The circuit as a DOT graph, coloured by its state after `presses` if given.
+pub fn network_dot(input: &Input, presses: Option<usize>) -> String {
+    let mut circuit = input.clone();
+    for _ in 0..presses.unwrap_or(0) {
+        circuit.press(None);
+    }
+    circuit.to_dot(presses.is_some())
+}
 
-    (lo_cntr, hi_cntr)
+#[aoc_generator(day20)]
+pub fn load_input(input: &str) -> Result<Input, ParseError> {
+    let mut modules: Vec<(String, Box<dyn Module>, Vec<String>)> = vec![];
+    // Each module's line and name, to point errors back at
+    let mut declarations = vec![];
+    for line in parse::lines(20, input) {
+        // First scan the name of the entity, then the list of destination entities
+        let (entity, dsts) = line.split_once(line.text, " -> ")?;
+        let dsts: Vec<String> = dsts.split(", ").map(|s| s.to_string()).collect();
+        let (id, module): (&str, Box<dyn Module>) = if let Some(id) = entity.strip_prefix('%') {
+            (id, Box::new(FlipFlop::default()))
+        } else if let Some(id) = entity.strip_prefix('&') {
+            (id, Box::new(Conjunction::default()))
+        } else if entity == "broadcaster" {
+            (entity, Box::new(Broadcaster))
+        } else {
+            return Err(line.error(entity, "`%name`, `&name` or `broadcaster`"));
+        };
+        modules.push((id.to_string(), module, dsts));
+        declarations.push((line, id));
+    }
+    Circuit::new(modules).map_err(|e| match e {
+        CircuitError::Duplicate(i) => {
+            let (line, id) = declarations[i];
+            line.error(id, &e.to_string())
+        }
+        // Missing, so point past the end
+        CircuitError::NoBroadcaster => {
+            ParseError::new(20, declarations.len() + 1, 1, &e.to_string(), "")
+        }
+    })
 }
 
 #[aoc(day20, part1)]
 pub fn part1(input: &Input) -> usize {
-    let (mut ffs, mut cons, starts) = input.clone();
-    let mut lo_cntr = 0;
-    let mut hi_cntr = 0;
-    for _ in 0..1000 {
-        let (lo_cnt, hi_cnt) = push_button(&mut ffs, &mut cons, &starts, 0, false);
-        lo_cntr += lo_cnt;
-        hi_cntr += hi_cnt;
-    }
+    let (lo_cntr, hi_cntr) = pulses_after(input, 1000);
     lo_cntr * hi_cntr
 }
 
+/// The conjunction that sends to `rx` and the modules feeding it. `rx` gets a low pulse when
+/// every one of those has just sent the conjunction a high pulse.
+pub fn rx_feeder(input: &Input) -> Result<(String, Vec<String>), String> {
+    let rx = input.id("rx").ok_or("nothing sends to `rx`")?;
+    match input.inputs(rx)[..] {
+        [feeder] if input.module(feeder).kind() == "conjunction" => {
+            let mut inputs: Vec<_> = input
+                .inputs(feeder)
+                .into_iter()
+                .map(|i| input.name(i).to_string())
+                .collect();
+            inputs.sort();
+            Ok((input.name(feeder).to_string(), inputs))
+        }
+        [feeder] => Err(format!("`rx` is fed by a {}", input.module(feeder).kind())),
+        _ => Err("`rx` has more than one module feeding it".to_string()),
+    }
+}
+
+/// An input to the `rx` feeder sends it a high pulse on press `offset`, and then every `period`
+/// presses after that.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Schedule {
+    pub offset: u64,
+    pub period: u64,
+}
+
+/// Press the button until each of `inputs` has sent `feeder` a high pulse twice, which gives its
+/// schedule. Gives up after `max_presses`.
+pub fn measure_schedules(
+    input: &Input,
+    feeder: &str,
+    inputs: &[String],
+    max_presses: u64,
+) -> Result<Vec<Schedule>, String> {
+    let mut circuit = input.clone();
+    let mut seen: HashMap<&str, Vec<u64>> = HashMap::new();
+    let mut trace = vec![];
+    for presses in 1..=max_presses {
+        trace.clear();
+        circuit.press(Some(&mut trace));
+        for sig in trace
+            .iter()
+            .filter(|s| s.dst == feeder && s.pulse == Pulse::Hi)
+        {
+            let hits = seen
+                .entry(inputs.iter().find(|i| **i == sig.src).unwrap())
+                .or_default();
+            // Several pulses in one press still only count once
+            if hits.last() != Some(&presses) {
+                debug!(presses, %sig, "high pulse into the rx feeder");
+                hits.push(presses);
+            }
+        }
+        if inputs
+            .iter()
+            .all(|i| seen.get(i.as_str()).is_some_and(|h| h.len() >= 2))
+        {
+            let schedules = inputs
+                .iter()
+                .map(|i| {
+                    let hits = &seen[i.as_str()];
+                    Schedule {
+                        offset: hits[0],
+                        period: hits[1] - hits[0],
+                    }
+                })
+                .collect();
+            return Ok(schedules);
+        }
+    }
+    Err(format!(
+        "not every input to `{}` repeated within {} presses",
+        feeder, max_presses
+    ))
+}
+
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        (a, 1, 0)
+    } else {
+        let (g, x, y) = extended_gcd(b, a % b);
+        (g, y, x - (a / b) * y)
+    }
+}
+
+/// The first press on which every schedule fires together, by the Chinese remainder theorem
+/// (which is just the LCM when every offset equals its period). The periods don't have to be
+/// coprime. `None` if they never line up.
+pub fn first_common_press(schedules: &[Schedule]) -> Option<u64> {
+    // Everything so far fires together on presses x, x + m, x + 2m, ...
+    let (mut x, mut m) = (0i128, 1i128);
+    for s in schedules {
+        let (a, n) = ((s.offset % s.period) as i128, s.period as i128);
+        let (g, inv, _) = extended_gcd(m, n);
+        if (a - x) % g != 0 {
+            return None;
+        }
+        let k = ((a - x) / g * inv).rem_euclid(n / g);
+        x += m * k;
+        m = m / g * n;
+        x = x.rem_euclid(m);
+    }
+    // Nothing fires before its first press
+    let first = schedules
+        .iter()
+        .map(|s| s.offset as i128)
+        .max()
+        .unwrap_or(1);
+    if x < first {
+        x += (first - x + m - 1) / m * m;
+    }
+    u64::try_from(x).ok()
+}
+
 #[aoc(day20, part2)]
-/// TODO: probably have to create something like a "cycle tree". Rough idea is work out the
-/// dependency graph of the entities as a tree, figure out how many button presses to cycle the
-/// state of subtrees, then math out the rest.
-///
-/// Basically "rx" is just flipped "th", and "th" is conjunction of 4 other conjunctions:
-/// "xn", "qn", "xf", "zl". I print out when those spit out a low pulse in # of button presses and
-/// we see that each is cyclic with some different cycle length. If you do modulus of any of their
-/// outputs you see its the cycle length - 1, so you can count on them all cycling at the same time
-/// at the LCM of their cycle lengths.
-///
-/// Cycles:
-/// "xn" - 4027
-/// "qn" - 3793
-/// "xf" - 3923
-/// "zl" - 3739
-///
-/// LCM (and answer to part 2): 224,046,542,165,867
-pub fn part2(_input: &Input) -> usize {
-    224046542165867
+/// `rx` is fed by one conjunction, which is in turn fed by a few separate counters that each
+/// send it a high pulse on a regular schedule. Measure those and work out when they coincide.
+pub fn part2(input: &Input) -> u64 {
+    let (feeder, inputs) = rx_feeder(input).unwrap_or_else(|e| panic!("{}", e));
+    let schedules =
+        measure_schedules(input, &feeder, &inputs, 1_000_000).unwrap_or_else(|e| panic!("{}", e));
+    debug!(?schedules);
+    first_common_press(&schedules).expect("the inputs to the rx feeder never line up")
+}
+
+pub struct Day20;
+
+impl Solution for Day20 {
+    type Input = Input;
+
+    const DAY: u32 = 20;
+
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        load_input(input)
+    }
+
+    fn part1(input: &Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Input) -> Answer {
+        part2(input).into()
+    }
+
+    fn graph(input: &Input, steps: Option<usize>) -> Option<String> {
+        Some(network_dot(input, steps))
+    }
 }
 
 #[cfg(test)]
@@ -286,11 +777,232 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2023/20a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 32000000);
 
         let input = read_to_string("input/2023/20b.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 11687500);
+        assert!(rx_feeder(&input).is_err());
+    }
+
+    #[derive(Clone, Debug)]
+    struct Inverter;
+
+    impl Module for Inverter {
+        fn kind(&self) -> &'static str {
+            "inverter"
+        }
+
+        fn receive(&mut self, _from: usize, pulse: Pulse) -> Option<Pulse> {
+            Some(if pulse == Pulse::Lo {
+                Pulse::Hi
+            } else {
+                Pulse::Lo
+            })
+        }
+
+        fn clone_box(&self) -> Box<dyn Module> {
+            Box::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn test_circuit() {
+        let input = read_to_string("input/2023/20a.txt").unwrap();
+        let mut circuit = load_input(&input).unwrap();
+        let mut trace = vec![];
+        assert_eq!(circuit.press(Some(&mut trace)), (8, 4));
+        assert_eq!(trace.len(), 12);
+        assert_eq!(trace[0].to_string(), "button -Lo-> broadcaster");
+        assert_eq!(trace[11].to_string(), "inv -Hi-> a");
+        let into_inv: Vec<_> = trace.iter().filter(|s| s.dst == "inv").collect();
+        assert_eq!(
+            into_inv,
+            vec![
+                &Signal::new("c", "inv", Pulse::Hi),
+                &Signal::new("c", "inv", Pulse::Lo)
+            ]
+        );
+
+        // A single input conjunction is just an inverter
+        let wire = |name: &str, module: Box<dyn Module>, outputs: &[&str]| {
+            let outputs = outputs.iter().map(|o| o.to_string()).collect();
+            (name.to_string(), module, outputs)
+        };
+        let flip = || Box::new(FlipFlop::default());
+        let circuit = Circuit::new(vec![
+            wire("broadcaster", Box::new(Broadcaster), &["a", "b", "c"]),
+            wire("a", flip(), &["b"]),
+            wire("b", flip(), &["c"]),
+            wire("c", flip(), &["inv"]),
+            wire("inv", Box::new(Inverter), &["a"]),
+        ])
+        .unwrap();
+        assert_eq!(
+            circuit.module(circuit.id("inv").unwrap()).kind(),
+            "inverter"
+        );
+        assert_eq!(part1(&circuit), 32000000);
+
+        let input = read_to_string("input/2023/20b.txt").unwrap();
+        let mut circuit = load_input(&input).unwrap();
+        assert_eq!(circuit.name(0), "broadcaster");
+        let output = circuit.id("output").unwrap();
+        assert_eq!(circuit.module(output).kind(), "output");
+        circuit.push_button();
+        let mut steps = vec![];
+        while let Some(sig) = circuit.step() {
+            steps.push(sig);
+        }
+        assert_eq!(steps.len(), 8);
+        assert_eq!(steps.last(), Some(&Signal::new("con", "output", Pulse::Lo)));
+        assert_eq!(circuit.step(), None);
+        let err = load_input("%a -> b").unwrap_err();
+        assert_eq!(err.expected, "a `broadcaster` module");
+        assert_eq!(err.line, 2);
+        let err = load_input("broadcaster -> a\n%a -> b\n&a -> b").unwrap_err();
+        assert_eq!((err.line, err.column), (3, 2));
+        let twice = vec![wire("a", flip(), &[]), wire("a", flip(), &[])];
+        assert_eq!(Circuit::new(twice).unwrap_err(), CircuitError::Duplicate(1));
+    }
+
+    #[test]
+    fn test_dot() {
+        let input = read_to_string("input/2023/20b.txt").unwrap();
+        let input = load_input(&input).unwrap();
+        let dot = network_dot(&input, None);
+        assert!(dot.starts_with("digraph circuit {"));
+        assert!(dot.contains("\"broadcaster\" [shape=doubleoctagon];"));
+        assert!(dot.contains("\"a\" [shape=box];"));
+        assert!(dot.contains("\"con\" [shape=invtrapezoid];"));
+        assert!(dot.contains("\"output\" [shape=doublecircle];"));
+        assert!(dot.contains("\"button\" -> \"broadcaster\";"));
+        assert!(dot.contains("\"a\" -> \"inv\";\n    \"a\" -> \"con\";"));
+        assert_eq!(dot.matches(" -> ").count(), 7);
+
+        // After one press both flip-flops are on, so `con` remembers two highs
+        let dot = network_dot(&input, Some(1));
+        assert!(dot.contains("\"a\" [shape=box, style=filled, fillcolor=\"#ff8080\"];"));
+        assert!(dot.contains("\"inv\" [shape=invtrapezoid, style=filled, fillcolor=\"#ff8080\"];"));
+        assert!(dot.contains("label=\"con\\n2/2\""));
+    }
+
+    #[test]
+    fn test_cycles() {
+        assert_eq!(Snapshot::pack([true, false, true]), Snapshot(vec![5]));
+        let bits = (0..65).map(|i| i % 64 == 0);
+        assert_eq!(Snapshot::pack(bits), Snapshot(vec![1, 1]));
+
+        let input = read_to_string("input/2023/20a.txt").unwrap();
+        let input = load_input(&input).unwrap();
+        assert_eq!(
+            find_cycle(&input, 10),
+            Some(Cycle {
+                start: 0,
+                period: 1
+            })
+        );
+        assert_eq!(
+            pulses_after(&input, 1_000_000_000_000),
+            (8_000_000_000_000, 4_000_000_000_000)
+        );
+        let names = |n: &[&str]| n.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            subcircuit_cycles(&input, 10),
+            vec![
+                (
+                    names(&["broadcaster"]),
+                    Some(Cycle {
+                        start: 0,
+                        period: 1
+                    })
+                ),
+                (
+                    names(&["a", "b", "c", "inv"]),
+                    Some(Cycle {
+                        start: 0,
+                        period: 1
+                    })
+                ),
+            ]
+        );
+
+        let input = read_to_string("input/2023/20b.txt").unwrap();
+        let input = load_input(&input).unwrap();
+        assert_eq!(
+            find_cycle(&input, 10),
+            Some(Cycle {
+                start: 0,
+                period: 4
+            })
+        );
+        assert_eq!(find_cycle(&input, 3), None);
+        assert_eq!(pulses_after(&input, 1000), (4250, 2750));
+        assert_eq!(pulses_after(&input, 1001), (4254, 2754));
+
+        // Each counter cycles on its own, but the whole thing takes their LCM
+        let input = load_input(
+            "broadcaster -> a, b\n%a -> a2\n%a2 -> a3, ca\n%a3 -> ca\n&ca -> a\n%b -> b2\n%b2 -> b",
+        )
+        .unwrap();
+        let cycles: Vec<_> = subcircuit_cycles(&input, 100)
+            .into_iter()
+            .map(|(_, c)| c.unwrap())
+            .collect();
+        assert_eq!(
+            cycles,
+            vec![
+                Cycle {
+                    start: 0,
+                    period: 1
+                },
+                Cycle {
+                    start: 1,
+                    period: 3
+                },
+                Cycle {
+                    start: 0,
+                    period: 7
+                }
+            ]
+        );
+        assert_eq!(
+            find_cycle(&input, 100),
+            Some(Cycle {
+                start: 1,
+                period: 21
+            })
+        );
+    }
+
+    #[test]
+    fn test_part2() {
+        let schedule = |offset, period| Schedule { offset, period };
+        assert_eq!(
+            first_common_press(&[schedule(4027, 4027), schedule(3793, 3793)]),
+            Some(4027 * 3793)
+        );
+        // 2 mod 3 and 3 mod 4
+        assert_eq!(
+            first_common_press(&[schedule(2, 3), schedule(3, 4)]),
+            Some(11)
+        );
+        assert_eq!(
+            first_common_press(&[schedule(6, 4), schedule(4, 6)]),
+            Some(10)
+        );
+        assert_eq!(first_common_press(&[schedule(1, 4), schedule(2, 6)]), None);
+
+        let input = load_input(
+            "broadcaster -> a, b\n%a -> ca\n&ca -> feed\n%b -> b2\n%b2 -> cb\n&cb -> feed\n&feed -> rx",
+        )
+        .unwrap();
+        let (feeder, inputs) = rx_feeder(&input).unwrap();
+        assert_eq!(feeder, "feed");
+        let schedules = measure_schedules(&input, &feeder, &inputs, 10).unwrap();
+        // `a` turns off every other press, `b2` every fourth
+        assert_eq!(schedules, vec![schedule(2, 2), schedule(4, 4)]);
+        assert_eq!(part2(&input), 4);
     }
 }
@@ -1,48 +1,58 @@
+use crate::direction::Direction;
+use crate::grid::Grid;
+use crate::parse::{self, ParseError};
+use crate::solution::{Answer, Solution};
+use crate::viewer::{Frame, Palette, Rgb};
 use std::collections::HashMap;
 
-pub type Input = HashMap<(i64, i64), char>;
+pub type Input = Grid<char>;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
-pub enum Direction {
-    North,
-    East,
-    South,
-    West,
-}
-
-pub fn check_position_free(pos: (i64, i64), map: &HashMap<(i64, i64), char>) -> bool {
-    if let Some(n) = map.get(&pos) {
+pub fn check_position_free(pos: (i64, i64), map: &Grid<char>) -> bool {
+    if let Some(n) = map.get(pos) {
         *n == '.' || *n == 'S'
     } else {
         false
     }
 }
 
-pub fn get_neighbors(pos: (i64, i64), map: &HashMap<(i64, i64), char>) -> Vec<(i64, i64)> {
-    let mut neighbors = vec![];
-    let positions = vec![
-        (pos.0, pos.1 - 1),
-        (pos.0 + 1, pos.1),
-        (pos.0, pos.1 + 1),
-        (pos.0 - 1, pos.1),
-    ];
-    for candidate_position in positions {
-        if check_position_free(candidate_position, map) {
-            neighbors.push(candidate_position);
-        }
-    }
-    neighbors
+pub fn get_neighbors(pos: (i64, i64), map: &Grid<char>) -> Vec<(i64, i64)> {
+    Direction::ALL
+        .iter()
+        .map(|d| d.offset(pos, 1))
+        .filter(|p| check_position_free(*p, map))
+        .collect()
 }
 
 #[aoc_generator(day21)]
-pub fn load_input(input: &str) -> Input {
-    let mut map: HashMap<(i64, i64), char> = HashMap::new();
-    for (y, line) in input.lines().enumerate() {
-        for (x, c) in line.chars().enumerate() {
-            map.insert((x as i64, y as i64), c);
+pub fn load_input(input: &str) -> Result<Input, ParseError> {
+    let lines: Vec<_> = parse::lines(21, input).collect();
+    let grid = Grid::parse(lines.iter().copied(), ".#S")?;
+    // Part 2 tiles the garden outwards from `S`, so it has to be a square with `S` in the middle
+    if grid.height() != grid.width() || grid.width() % 2 == 0 {
+        let line = lines.last().map_or("", |l| l.text);
+        let expected = format!(
+            "an odd number of rows as long as the first ({})",
+            grid.width()
+        );
+        return Err(ParseError::new(21, lines.len().max(1), 1, &expected, line));
+    }
+    let middle = (grid.width() / 2, grid.height() / 2);
+    match grid.positions().find(|&p| grid[p] == 'S') {
+        Some((x, y)) if (x, y) != middle => {
+            let line = lines[y];
+            Err(line.error(&line.text[x..], "`S` in the middle of the garden"))
+        }
+        Some(_) => Ok(grid),
+        None => {
+            let line = lines[middle.1];
+            Err(line.error(&line.text[middle.0..], "`S`"))
         }
     }
-    map
+}
+
+// Where the elf starts, which `load_input` made sure is the middle of the garden.
+fn start(input: &Input) -> (i64, i64) {
+    (input.width() as i64 / 2, input.height() as i64 / 2)
 }
 
 pub fn solve_part1(input: &Input, nsteps: usize) -> usize {
@@ -50,18 +60,14 @@ pub fn solve_part1(input: &Input, nsteps: usize) -> usize {
 
     // Create a neighbors cache
     let mut neighbors_cache: HashMap<(i64, i64), Vec<(i64, i64)>> = HashMap::new();
-    for (pos, tile) in map.iter() {
+    for ((x, y), tile) in map.iter() {
         if *tile == '.' || *tile == 'S' {
-            neighbors_cache.insert(*pos, get_neighbors(*pos, &map));
+            let pos = (x as i64, y as i64);
+            neighbors_cache.insert(pos, get_neighbors(pos, &map));
         }
     }
 
-    let start_position = map
-        .iter()
-        .filter(|(_k, &v)| v == 'S')
-        .map(|(k, _)| k)
-        .collect::<Vec<_>>()[0];
-    let mut even_positions: Vec<(i64, i64)> = vec![*start_position];
+    let mut even_positions: Vec<(i64, i64)> = vec![start(&map)];
     let mut odd_positions: Vec<(i64, i64)> = vec![];
     for i in 0..nsteps {
         //println!("i: {}", i);
@@ -88,32 +94,10 @@ pub fn solve_part1(input: &Input, nsteps: usize) -> usize {
             }
         }
     }
-    //print_map(&even_positions, &map);
     //println!("even_positions: {:?}", even_positions);
     even_positions.len()
 }
 
-#[allow(dead_code)]
-fn print_map(highlight: &[(i64, i64)], tilemap: &HashMap<(i64, i64), char>) {
-    let xmax = *tilemap.keys().map(|(x, _)| x).max().unwrap();
-    let ymax = *tilemap.keys().map(|(_, y)| y).max().unwrap();
-    for y in 0..ymax + 1 {
-        for x in 0..xmax + 1 {
-            let mut color = false;
-            if highlight.contains(&(x, y)) {
-                color = true;
-            }
-            let cc = tilemap.get(&(x, y)).unwrap();
-            if color {
-                print!("\x1b[91m{}\x1b[0m", *cc);
-            } else {
-                print!("{}", *cc);
-            }
-        }
-        println!();
-    }
-}
-
 #[aoc(day21, part1)]
 pub fn part1(input: &Input) -> usize {
     solve_part1(input, 64)
@@ -125,13 +109,29 @@ pub fn part2(input: &Input) -> i64 {
 }
 
 pub fn simulate(input: &Input, nsteps: usize, start_position: (i64, i64)) -> Vec<(i64, i64)> {
+    simulate_with(input, nsteps, start_position, |_, _| ())
+}
+
+/// `simulate`, calling `on_step` with the step number and the plots reachable in exactly that
+/// many steps.
+pub fn simulate_with(
+    input: &Input,
+    nsteps: usize,
+    start_position: (i64, i64),
+    mut on_step: impl FnMut(usize, &[(i64, i64)]),
+) -> Vec<(i64, i64)> {
     let map = input.clone();
+    // Nobody can stand on a rock
+    if !check_position_free(start_position, &map) {
+        return vec![];
+    }
 
     // Create a neighbors cache
     let mut neighbors_cache: HashMap<(i64, i64), Vec<(i64, i64)>> = HashMap::new();
-    for (pos, tile) in map.iter() {
+    for ((x, y), tile) in map.iter() {
         if *tile == '.' || *tile == 'S' {
-            neighbors_cache.insert(*pos, get_neighbors(*pos, &map));
+            let pos = (x as i64, y as i64);
+            neighbors_cache.insert(pos, get_neighbors(pos, &map));
         }
     }
 
@@ -163,12 +163,31 @@ pub fn simulate(input: &Input, nsteps: usize, start_position: (i64, i64)) -> Vec
                 }
             }
         }
+        if i % 2 == 0 {
+            on_step(i + 1, &odd_positions);
+        } else {
+            on_step(i + 1, &even_positions);
+        }
     }
-    //print_map(&even_positions, &map);
     //println!("even_positions: {:?}", even_positions);
     even_positions
 }
 
+/// The part 1 walk, highlighting the plots the elf could be on after each step.
+pub fn step_frames(input: &Input, nsteps: usize) -> Vec<Frame> {
+    let mut frames = vec![Frame::new(input.clone()).with_caption("step 0: 1 plot")];
+    simulate_with(input, nsteps, start(input), |step, positions| {
+        let caption = format!("step {}: {} plots", step, positions.len());
+        let cells = positions.iter().map(|&(x, y)| (x as usize, y as usize));
+        frames.push(
+            Frame::new(input.clone())
+                .with_highlight(cells)
+                .with_caption(caption),
+        );
+    });
+    frames
+}
+
 // 613473117038252 is too high
 pub fn solve_part2(input: &Input, nsteps: usize) -> i64 {
     // make an infinite field of diamond patterns, limit to the # of steps (diamond shape because
@@ -176,10 +195,9 @@ pub fn solve_part2(input: &Input, nsteps: usize) -> i64 {
     // modulus math but how to determine how many fields to remove in a go? or maybe just be dumb
     // and actually iterate x+y=bignumber? No, do not just iterate.
     let thenum = nsteps as i64;
-    let map = input.clone();
-    let xmax = map.keys().map(|(x, _y)| x).max().unwrap();
+    let xmax = input.width() as i64 - 1;
     let half_field = xmax / 2;
-    let field_width = *xmax + 1;
+    let field_width = xmax + 1;
     let n_fields = (thenum - half_field) / field_width;
     let nn = (thenum + 1) / field_width;
     let full_0 = nn.pow(2);
@@ -203,62 +221,85 @@ pub fn solve_part2(input: &Input, nsteps: usize) -> i64 {
     let mut output = 0;
 
     // Handle the middle
-    let full_field0 = simulate(input, 260, (0, 0));
-    //print_map(&full_field0, &map);
+    let full_field0 = simulate(input, 2 * xmax as usize, (0, 0));
     output += full_0 * full_field0.len() as i64;
-    let full_field1 = simulate(input, 260, (0, 1));
-    //print_map(&full_field1, &map);
+    let full_field1 = simulate(input, 2 * xmax as usize, (0, 1));
     output += full_1 * full_field1.len() as i64;
 
     // Handle the four tips
-    let northern_tip = simulate(input, 130, (65, 130));
-    //print_map(&northern_tip, &map);
+    let northern_tip = simulate(input, xmax as usize, (half_field, xmax));
     output += northern_tip.len() as i64;
 
-    let southern_tip = simulate(input, 130, (65, 0));
-    //print_map(&southern_tip, &map);
+    let southern_tip = simulate(input, xmax as usize, (half_field, 0));
     output += southern_tip.len() as i64;
 
-    let eastern_tip = simulate(input, 130, (0, 65));
-    //print_map(&eastern_tip, &map);
+    let eastern_tip = simulate(input, xmax as usize, (0, half_field));
     output += eastern_tip.len() as i64;
 
-    let western_tip = simulate(input, 130, (130, 65));
-    //print_map(&western_tip, &map);
+    let western_tip = simulate(input, xmax as usize, (xmax, half_field));
     output += western_tip.len() as i64;
 
     // Handle the edges
-    let southeast0 = simulate(input, 130 + 64, (0, 1));
-    //print_map(&southeast0, &map);
-    let southeast1 = simulate(input, 65, (0, 0));
-    //print_map(&southeast1, &map);
+    let southeast0 = simulate(input, (xmax + half_field - 1).max(0) as usize, (0, 1));
+    let southeast1 = simulate(input, half_field as usize, (0, 0));
     output += (n_fields - 1) * southeast0.len() as i64;
     output += n_fields * southeast1.len() as i64;
 
-    let northeast0 = simulate(input, 130 + 64, (0, 129));
-    let northeast1 = simulate(input, 65, (0, 130));
-    //print_map(&northeast1, &map);
-    //print_map(&northeast0, &map);
+    let northeast0 = simulate(
+        input,
+        (xmax + half_field - 1).max(0) as usize,
+        (0, xmax - 1),
+    );
+    let northeast1 = simulate(input, half_field as usize, (0, xmax));
     output += (n_fields - 1) * northeast0.len() as i64;
     output += n_fields * northeast1.len() as i64;
 
-    let southwest0 = simulate(input, 130 + 64, (130, 1));
-    //print_map(&southwest0, &map);
-    let southwest1 = simulate(input, 65, (130, 0));
-    //print_map(&southwest1, &map);
+    let southwest0 = simulate(input, (xmax + half_field - 1).max(0) as usize, (xmax, 1));
+    let southwest1 = simulate(input, half_field as usize, (xmax, 0));
     output += (n_fields - 1) * southwest0.len() as i64;
     output += n_fields * southwest1.len() as i64;
 
-    let northwest0 = simulate(input, 130 + 64, (130, 129));
-    let northwest1 = simulate(input, 65, (130, 130));
-    //print_map(&northwest1, &map);
-    //print_map(&northwest0, &map);
+    let northwest0 = simulate(
+        input,
+        (xmax + half_field - 1).max(0) as usize,
+        (xmax, xmax - 1),
+    );
+    let northwest1 = simulate(input, half_field as usize, (xmax, xmax));
     output += (n_fields - 1) * northwest0.len() as i64;
     output += n_fields * northwest1.len() as i64;
 
     output
 }
 
+pub struct Day21;
+
+impl Solution for Day21 {
+    type Input = Input;
+
+    const DAY: u32 = 21;
+
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        load_input(input)
+    }
+
+    fn part1(input: &Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Input) -> Answer {
+        part2(input).into()
+    }
+
+    fn frames(input: &Input) -> Vec<Frame> {
+        step_frames(input, 64)
+    }
+
+    fn palette() -> Palette {
+        Palette::new(&[('#', Rgb(90, 110, 90)), ('.', Rgb(60, 60, 60))])
+            .with_highlight(Rgb(120, 220, 120))
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -267,34 +308,52 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2023/21a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(solve_part1(&input, 6), 16);
     }
 
+    #[test]
+    fn test_errors() {
+        assert!(load_input("").is_err());
+        let err = load_input("...\n.S.\n...\n...").unwrap_err();
+        assert_eq!(err.line, 4);
+        let err = load_input("S..\n...\n...").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 1));
+        let err = load_input("...\n.#.\n...").unwrap_err();
+        assert_eq!((err.line, err.column, err.expected.as_str()), (2, 2, "`S`"));
+        // The corners part 2 starts from can be rocks
+        let input = load_input("#.#\n.S.\n#.#").unwrap();
+        assert_eq!(simulate(&input, 2, (0, 0)), vec![]);
+        assert_eq!(solve_part1(&input, 2), 1);
+        solve_part2(&input, 7);
+        let input = load_input("S").unwrap();
+        solve_part2(&input, 7);
+    }
+
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2023/21a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(solve_part2(&input, 10), 50);
 
         let input = read_to_string("input/2023/21a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(solve_part2(&input, 50), 1594);
 
         let input = read_to_string("input/2023/21a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(solve_part2(&input, 100), 6536);
 
         let input = read_to_string("input/2023/21a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(solve_part2(&input, 500), 167004);
 
         let input = read_to_string("input/2023/21a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(solve_part2(&input, 1000), 668697);
 
         let input = read_to_string("input/2023/21a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(solve_part2(&input, 5000), 16733044);
     }
 }
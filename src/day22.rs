@@ -1,3 +1,5 @@
+use crate::parse::{self, ParseError};
+use crate::solution::{Answer, Solution};
 use rayon::prelude::*;
 use std::collections::HashSet;
 
@@ -88,27 +90,21 @@ impl Brick {
 }
 
 #[aoc_generator(day22)]
-pub fn load_input(input: &str) -> Input {
+pub fn load_input(input: &str) -> Result<Input, ParseError> {
     let mut bricks = vec![];
-    for line in input.lines() {
-        let mut temp = line.split('~');
-        let dim1: Vec<i64> = temp
-            .next()
-            .unwrap()
-            .split(',')
-            .map(|c| c.parse::<i64>().unwrap())
-            .collect();
-        let dim1 = (dim1[0], dim1[1], dim1[2]);
-        let dim2: Vec<i64> = temp
-            .next()
-            .unwrap()
-            .split(',')
-            .map(|c| c.parse::<i64>().unwrap())
-            .collect();
-        let dim2 = (dim2[0], dim2[1], dim2[2]);
+    for line in parse::lines(22, input) {
+        let (dim1, dim2) = line.split_once(line.text, "~")?;
+        let dim1 = match line.list::<i64>(dim1, ',')?[..] {
+            [x, y, z] => (x, y, z),
+            _ => return Err(line.error(dim1, "`x,y,z`")),
+        };
+        let dim2 = match line.list::<i64>(dim2, ',')?[..] {
+            [x, y, z] => (x, y, z),
+            _ => return Err(line.error(dim2, "`x,y,z`")),
+        };
         bricks.push(Brick::new(dim1, dim2));
     }
-    bricks
+    Ok(bricks)
 }
 
 #[allow(dead_code)]
@@ -243,6 +239,26 @@ pub fn part2(input: &Input) -> i64 {
         .sum()
 }
 
+pub struct Day22;
+
+impl Solution for Day22 {
+    type Input = Input;
+
+    const DAY: u32 = 22;
+
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        load_input(input)
+    }
+
+    fn part1(input: &Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Input) -> Answer {
+        part2(input).into()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -259,14 +275,14 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2023/22a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 5);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2023/22a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 7);
     }
 }
@@ -1,43 +1,88 @@
+use crate::grid::Grid;
+use crate::parse::{self, ParseError};
+use crate::solution::{Answer, Solution, Status};
+use crate::viewer::{Frame, Palette, Rgb};
 use aoc_helpers::tree::Tree;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-pub type Input = HashMap<(usize, usize), char>;
+pub type Input = Grid<char>;
 
 #[aoc_generator(day23)]
-pub fn load_input(input: &str) -> Input {
-    let mut output = HashMap::new();
-    for (y, line) in input.lines().enumerate() {
-        for (x, c) in line.chars().enumerate() {
-            output.insert((x, y), c);
-        }
+pub fn load_input(input: &str) -> Result<Input, ParseError> {
+    let lines: Vec<_> = parse::lines(23, input).collect();
+    let grid = Grid::parse(lines.iter().copied(), "#.<>^v")?;
+    // Every tile is ASCII, so x is also the byte offset into its line
+    let error = |(x, y): (usize, usize), expected: &str| {
+        let line = lines[y];
+        line.error(&line.text[x..], expected)
+    };
+    let (width, height) = (grid.width(), grid.height());
+    if height < 3 {
+        return Err(ParseError::new(
+            23,
+            height + 1,
+            1,
+            "at least three rows",
+            "",
+        ));
+    }
+    if width < 3 {
+        return Err(lines[0].error_after(lines[0].text, "at least three tiles"));
     }
-    output
-}
 
-#[allow(dead_code)]
-fn print_map(highlight: &[(usize, usize)], map: &Input) {
-    let xmax = map.keys().map(|(x, _y)| *x).max().unwrap();
-    let ymax = map.keys().map(|(_x, y)| *y).max().unwrap();
-    for y in 0..ymax + 1 {
-        for x in 0..xmax + 1 {
-            let mut color = false;
-            if highlight.contains(&(x, y)) {
-                color = true;
+    // The forest is hedged in apart from one `.` in the top row and one in the bottom
+    let mut gaps: Vec<(usize, usize)> = vec![];
+    for (x, y) in grid.positions() {
+        let edge = x == 0 || y == 0 || x == width - 1 || y == height - 1;
+        if !edge || grid[(x, y)] == '#' {
+            continue;
+        }
+        if x == 0 || x == width - 1 || grid[(x, y)] != '.' || gaps.iter().any(|g| g.1 == y) {
+            return Err(error((x, y), "`#`, the way in and out is a single `.`"));
+        }
+        gaps.push((x, y));
+    }
+    let [start, end] = gaps[..] else {
+        let y = if gaps.first().is_some_and(|g| g.1 == 0) {
+            height - 1
+        } else {
+            0
+        };
+        return Err(error((0, y), "a `.` to go in or out by"));
+    };
+
+    // Paths never dead end or lead off a slope into the trees
+    for p in grid.positions().filter(|&p| grid[p] != '#') {
+        if let [next] = get_neighbors(p, &grid, false)[..] {
+            if grid[p] != '.' && grid[next] == '#' {
+                return Err(error(p, "a slope down onto a path"));
             }
-            let c = map.get(&(x, y)).unwrap();
-            if color {
-                print!("\x1b[91m{}\x1b[0m", *c);
-            } else {
-                print!("{}", *c);
+        }
+        let open = grid.neighbors4(p).filter(|&n| grid[n] != '#').count();
+        if p != start && p != end && open < 2 {
+            return Err(error(p, "a path that doesn't dead end"));
+        }
+    }
+
+    // Going down the slopes has to get somewhere
+    let mut seen = HashSet::from([start]);
+    let mut todo = vec![start];
+    while let Some(p) = todo.pop() {
+        for n in get_neighbors(p, &grid, false) {
+            if seen.insert(n) {
+                todo.push(n);
             }
         }
-        println!();
     }
+    if !seen.contains(&end) {
+        return Err(error(end, "an exit the slopes lead to"));
+    }
+    Ok(grid)
 }
 
 pub fn get_neighbors(point: (usize, usize), map: &Input, part2: bool) -> Vec<(usize, usize)> {
     // Handle slopes right away
-    let p = *map.get(&point).unwrap();
+    let p = map[point];
     if !part2 {
         if p == '<' {
             return vec![(point.0 - 1, point.1)];
@@ -50,25 +95,9 @@ pub fn get_neighbors(point: (usize, usize), map: &Input, part2: bool) -> Vec<(us
         }
     }
 
-    let xmax = map.keys().map(|(x, _y)| *x).max().unwrap();
-    let ymax = map.keys().map(|(_x, y)| *y).max().unwrap();
-    let mut candidates = vec![];
-    if point.0 > 0 {
-        candidates.push((point.0 - 1, point.1));
-    }
-    if point.0 < xmax {
-        candidates.push((point.0 + 1, point.1));
-    }
-    if point.1 > 0 {
-        candidates.push((point.0, point.1 - 1));
-    }
-    if point.1 < ymax {
-        candidates.push((point.0, point.1 + 1));
-    }
-
     let mut output = vec![];
-    for cand in candidates {
-        let c = map.get(&cand).unwrap();
+    for cand in map.neighbors4(point) {
+        let c = &map[cand];
         if part2 {
             if *c != '#' {
                 output.push(cand);
@@ -121,13 +150,13 @@ pub fn find_save_points(input: &Input) -> HashMap<(usize, usize), Vec<(usize, us
 }
 
 pub fn find_save_points2(input: &Input) -> HashMap<(usize, usize), Vec<(usize, usize)>> {
-    let points: Vec<_> = input.iter().filter(|(_k, &v)| v == '.').collect();
+    let points: Vec<_> = input.iter().filter(|(_k, &v)| v != '#').collect();
 
     let mut output = HashMap::new();
     for (pt, _) in points {
-        let neighbors = get_neighbors(*pt, input, true);
+        let neighbors = get_neighbors(pt, input, true);
         if neighbors.len() > 2 {
-            output.insert(*pt, neighbors);
+            output.insert(pt, neighbors);
         }
     }
     output
@@ -135,6 +164,12 @@ pub fn find_save_points2(input: &Input) -> HashMap<(usize, usize), Vec<(usize, u
 
 // length includes the starting point and the next save point.
 pub fn path_length(start: (usize, usize), input: &Input) -> (usize, (usize, usize)) {
+    let (cells, dst) = corridor(start, input);
+    (cells.len(), dst)
+}
+
+// The cells walked from `start` to the next save point, including it.
+fn corridor(start: (usize, usize), input: &Input) -> (Vec<(usize, usize)>, (usize, usize)) {
     let mut frontier = vec![start];
     let mut explored: Vec<(usize, usize)> = vec![start];
     let mut last = start;
@@ -152,7 +187,7 @@ pub fn path_length(start: (usize, usize), input: &Input) -> (usize, (usize, usiz
                 .collect();
             if neighbors.len() > 1 {
                 // This is a "save point", a diverging path.
-                return (explored.len(), *pt);
+                return (explored, *pt);
             }
             for n in neighbors {
                 if !frontier.contains(&n) {
@@ -164,19 +199,21 @@ pub fn path_length(start: (usize, usize), input: &Input) -> (usize, (usize, usiz
         last = frontier[0];
         frontier = new_frontier;
     }
-    (explored.len(), last)
+    (explored, last)
 }
 
-#[aoc(day23, part1)]
-pub fn part1(input: &Input) -> usize {
+// Every part 1 hike, one node per corridor: where it starts, the save point it ends on, the length
+// of the hike so far and the node before.
+type HikeTree = Tree<((usize, usize), (usize, usize), usize, Option<usize>)>;
+
+fn hike_tree(input: &Input) -> HikeTree {
     // Explore the graph once keeping track of "save points"
     let save_points = find_save_points(input);
 
-    //print_map(&vec![], input);
-
     // Start path tree with the path to the first save point
-    let (length, dst) = path_length((1, 0), input);
-    let mut path_tree = Tree::with_head(((1, 0), dst, length));
+    let start = end_point(input, 0);
+    let (length, dst) = path_length(start, input);
+    let mut path_tree = Tree::with_head((start, dst, length, None));
 
     let mut cids = vec![0];
     loop {
@@ -192,7 +229,7 @@ pub fn part1(input: &Input) -> usize {
                 for start in paths {
                     let (length, next_dst) = path_length(*start, input);
                     let cid = path_tree
-                        .add_child_to_node((*start, next_dst, tree_length + length), pid)
+                        .add_child_to_node((*start, next_dst, tree_length + length, Some(pid)), pid)
                         .unwrap();
                     new_cids.push(cid);
                 }
@@ -200,36 +237,70 @@ pub fn part1(input: &Input) -> usize {
         }
         cids = new_cids;
     }
+    path_tree
+}
 
-    *path_tree
+#[aoc(day23, part1)]
+pub fn part1(input: &Input) -> usize {
+    *hike_tree(input)
         .leaf_values()
         .iter()
-        .map(|(_, _, l)| l)
+        .map(|(_, _, l, _)| l)
         .max()
         .unwrap()
         - 1
 }
 
+/// The cells of the longest hike for part 1, from the entrance to the exit.
+pub fn longest_hike(input: &Input) -> Vec<(usize, usize)> {
+    let tree = hike_tree(input);
+    // Hikes only get longer going down the tree, so the longest node is the end of the longest
+    let mut at = (0..tree.nodes.len()).max_by_key(|&i| tree.nodes[i].value.2);
+    let mut corridors = vec![];
+    while let Some(i) = at {
+        let (start, _, _, parent) = tree.nodes[i].value;
+        corridors.push(corridor(start, input).0);
+        at = parent;
+    }
+    corridors.into_iter().rev().flatten().collect()
+}
+
+// The single open tile in row `y`, used for the entrance and exit of the maze. `load_input` checks
+// they're there.
+fn end_point(input: &Input, y: usize) -> (usize, usize) {
+    (input.row(y).iter().position(|&c| c == '.').unwrap(), y)
+}
+
+/// The longest part 1 hike, drawn a bit at a time.
+pub fn hike_frames(input: &Input) -> Vec<Frame> {
+    let hike = longest_hike(input);
+    let step = (hike.len() / 200).max(1);
+    let mut frames = vec![];
+    for n in (1..hike.len()).step_by(step).chain([hike.len()]) {
+        frames.push(
+            Frame::new(input.clone())
+                .with_highlight(hike[..n].iter().copied())
+                .with_caption(format!("{} steps", n - 1)),
+        );
+    }
+    frames
+}
+
+// The walk from save point `from` through its neighbour `start` to the next save point, or back to
+// `from` if there isn't one.
 pub fn path_length2(
+    from: (usize, usize),
     start: (usize, usize),
     save_points: &HashMap<(usize, usize), Vec<(usize, usize)>>,
     input: &Input,
 ) -> (usize, (usize, usize)) {
     let mut frontier = vec![start];
-    let mut explored: Vec<(usize, usize)> = vec![start];
+    // Counting `from` as explored keeps us from heading back that way
+    let mut explored: Vec<(usize, usize)> = vec![from, start];
     let mut ends = vec![];
     let mut bail = false;
-    let start_map = input
-        .iter()
-        .filter(|(k, &v)| k.1 == 0 && v == '.')
-        .map(|(k, _v)| k)
-        .collect::<Vec<_>>()[0];
-    let ymax = input.keys().map(|(_x, y)| *y).max().unwrap();
-    let end_map = input
-        .iter()
-        .filter(|(k, &v)| k.1 == ymax && v == '.')
-        .map(|(k, _v)| k)
-        .collect::<Vec<_>>()[0];
+    let start_map = &end_point(input, 0);
+    let end_map = &end_point(input, input.height() - 1);
     loop {
         if frontier.is_empty() {
             break;
@@ -263,15 +334,10 @@ pub fn path_length2(
         frontier = new_frontier;
     }
 
-    // "ends" should have 2 save_points
-    for end in &ends {
-        if (end.0 as i64 - start.0 as i64).abs() + (end.1 as i64 - start.1 as i64).abs() > 1 {
-            return (explored.len() - 1, *end);
-        }
+    match ends.first() {
+        Some(&end) => (explored.len() - 1, end),
+        None => (0, from),
     }
-    println!("ends: {:?}", ends);
-    println!("start: {:?}", start);
-    panic!("path_length2 failed");
 }
 
 #[aoc(day23, part2)]
@@ -280,23 +346,23 @@ pub fn part2(input: &Input) -> usize {
     // Explore the graph once keeping track of "save points"
     let save_points = find_save_points2(input);
 
-    //print_map(&vec![], input);
-
     //println!("save_points.len(): {}", save_points.len());
     //println!("save_points: {:?}", save_points);
     //
-    let mut path_length2_cache: HashMap<(usize, usize), (usize, (usize, usize))> = HashMap::new();
-    for starts in save_points.values() {
+    // Keyed by save point and neighbour, since a short corridor can be next to two save points
+    let mut path_length2_cache: HashMap<_, (usize, (usize, usize))> = HashMap::new();
+    for (from, starts) in &save_points {
         for n in starts {
-            let (length, next_dst) = path_length2(*n, &save_points, input);
-            path_length2_cache.insert(*n, (length, next_dst));
+            let (length, next_dst) = path_length2(*from, *n, &save_points, input);
+            path_length2_cache.insert((*from, *n), (length, next_dst));
         }
     }
     //println!("path_length2_cache.len(): {}", path_length2_cache.len());
 
     // Start path tree with the path to the first save point
-    let (length, dst) = path_length2((1, 1), &save_points, input);
-    let mut path_tree = Tree::with_head(((1, 0), dst, length, vec![(1, 0), dst]));
+    let start = end_point(input, 0);
+    let (length, dst) = path_length2(start, (start.0, 1), &save_points, input);
+    let mut path_tree = Tree::with_head((start, dst, length, vec![start, dst]));
 
     let mut cids = vec![0];
     loop {
@@ -314,18 +380,18 @@ pub fn part2(input: &Input) -> usize {
                 //println!("choices: {:?}", paths);
                 for start in paths {
                     //println!("start: {:?}", start);
-                    //let (length, next_dst) = path_length2(*start, &save_points, input);
-                    let (length, next_dst) = path_length2_cache.get(start).unwrap();
+                    //let (length, next_dst) = path_length2(dst, *start, &save_points, input);
+                    let (length, next_dst) = path_length2_cache[&(dst, *start)];
                     //println!("destination: {:?}", next_dst);
                     let mut explored = path_tree.nodes[pid].value.3.clone();
 
-                    if !explored.contains(next_dst) {
+                    if !explored.contains(&next_dst) {
                         // If this path hasn't gone here before, add it as a branching child
                         //println!("next_dst: {:?}", next_dst);
-                        explored.push(*next_dst);
+                        explored.push(next_dst);
                         let cid = path_tree
                             .add_child_to_node(
-                                (*start, *next_dst, tree_length + length, explored),
+                                (*start, next_dst, tree_length + length, explored),
                                 pid,
                             )
                             .unwrap();
@@ -338,19 +404,45 @@ pub fn part2(input: &Input) -> usize {
     }
 
     //println!("path_tree: {:?}", path_tree);
-    let ymax = input.keys().map(|(_x, y)| *y).max().unwrap();
-    let end_point = input
-        .iter()
-        .filter(|(k, &v)| k.1 == ymax && v == '.')
-        .map(|(k, _v)| k)
-        .collect::<Vec<_>>()[0];
+    let end_point = end_point(input, input.height() - 1);
     *path_tree
         .leaf_values()
         .iter()
-        .filter(|(_, dst, _, _)| dst == end_point)
+        .filter(|(_, dst, _, _)| *dst == end_point)
         .map(|(_, _, l, _)| l)
         .max()
-        .unwrap()
+        .unwrap_or(&0)
+}
+
+pub struct Day23;
+
+impl Solution for Day23 {
+    type Input = Input;
+
+    const DAY: u32 = 23;
+    // Runs out of memory on the full input
+    const STATUS: [Status; 2] = [Status::Solved, Status::TooSlow];
+
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        load_input(input)
+    }
+
+    fn part1(input: &Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Input) -> Answer {
+        part2(input).into()
+    }
+
+    fn frames(input: &Input) -> Vec<Frame> {
+        hike_frames(input)
+    }
+
+    fn palette() -> Palette {
+        Palette::new(&[('#', Rgb(40, 90, 40)), ('.', Rgb(150, 120, 80))])
+            .with_highlight(Rgb(255, 255, 255))
+    }
 }
 
 #[cfg(test)]
@@ -361,14 +453,47 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2023/23a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 94);
+        let hike = longest_hike(&input);
+        assert_eq!(hike.len() - 1, 94);
+        assert!(hike
+            .windows(2)
+            .all(|w| input.neighbors4(w[0]).any(|n| n == w[1])));
+        assert_eq!(hike.last(), Some(&end_point(&input, input.height() - 1)));
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2023/23a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 154);
     }
+
+    #[test]
+    fn test_errors() {
+        assert!(load_input("").is_err());
+        assert!(load_input("#.#\n#.#").is_err());
+        let input = load_input("#.#\n#.#\n#.#").unwrap();
+        assert_eq!((part1(&input), part2(&input)), (2, 2));
+        // No way in
+        let err = load_input("###\n#.#\n#.#").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 1));
+        // Two ways out
+        let err = load_input("#.###\n#...#\n#.#.#").unwrap_err();
+        assert_eq!((err.line, err.column), (3, 4));
+        // Dead end
+        let err = load_input("#.###\n#..##\n#.###").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 3));
+        // Slope into the trees
+        let err = load_input("#.###\n#>#.#\n#.###").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 2));
+        // The only way down is uphill
+        let err = load_input("#.#\n#^#\n#.#").unwrap_err();
+        assert_eq!((err.line, err.column), (3, 2));
+
+        // Junctions right next to each other, and a loop
+        let input = load_input("#.###\n#...#\n#...#\n###.#").unwrap();
+        assert_eq!(part2(&input), 7);
+    }
 }
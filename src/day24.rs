@@ -1,117 +1,335 @@
+use crate::parse::{self, ParseError};
+use crate::solution::{Answer, Solution};
+use num::{BigInt, BigRational, Signed, ToPrimitive, Zero};
+use std::fmt;
+use std::ops::RangeInclusive;
+use tracing::debug;
+
 pub type Input = Vec<Snowflake>;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Snowflake {
-    pub pos: (f64, f64, f64),
-    pub vel: (f64, f64, f64),
+    pub pos: (i64, i64, i64),
+    pub vel: (i64, i64, i64),
 }
 
 impl Snowflake {
-    pub fn new(pos: (f64, f64, f64), vel: (f64, f64, f64)) -> Self {
+    pub fn new(pos: (i64, i64, i64), vel: (i64, i64, i64)) -> Self {
         Self { pos, vel }
     }
 
-    /// Evaluate position with parameterized t.
-    pub fn evaluate(&self, t: f64) -> (f64, f64, f64) {
-        let x = self.vel.0 * t + self.pos.0;
-        let y = self.vel.1 * t + self.pos.1;
-        let z = self.vel.2 * t + self.pos.2;
-        (x, y, z)
-    }
+    /// Where the paths of the two hailstones cross, ignoring z.
+    pub fn crossing_xy(&self, other: &Snowflake) -> Crossing {
+        // Integers all the way until the final division, which is much cheaper than rationals
+        let int = |n: i64| BigInt::from(n);
+        let (px, py) = (int(self.pos.0), int(self.pos.1));
+        let (vx, vy) = (int(self.vel.0), int(self.vel.1));
+        let (wx, wy) = (int(other.vel.0), int(other.vel.1));
+        let (dx, dy) = (int(other.pos.0) - &px, int(other.pos.1) - &py);
 
-    pub fn intersection_xy(&self, other: &Snowflake) -> Option<(f64, f64)> {
-        let t1 = (self.vel.1 * ((other.pos.0 - self.pos.0) / self.vel.0) + self.pos.1
-            - other.pos.1)
-            / (other.vel.1 * (1_f64 - (self.vel.1 * other.vel.0) / (other.vel.1 * self.vel.0)));
-        //println!("t1: {}", t1);
-        if t1 < 0.0 {
-            return None;
-        }
-        let t0 = (other.vel.0 * t1 + other.pos.0 - self.pos.0) / self.vel.0;
-        //println!("t0: {}", t0);
-        if t0 < 0.0 {
-            return None;
-        }
-        let p0 = self.evaluate(t0);
-        Some((p0.0, p0.1))
-    }
-
-    /// TODO: Part 1 wants _future_ intersections, disregard past.
-    pub fn intersect_in_bounds(&self, other: &Snowflake, low: f64, high: f64) -> bool {
-        // Verify if its even possible for X's to intersect in area in the future
-        if (self.pos.0 < low && self.vel.0 < 0.0)
-            || (self.pos.0 > high && self.vel.0 > 0.0)
-            || (other.pos.0 < low && other.vel.0 < 0.0)
-            || (other.pos.0 > high && other.vel.0 > 0.0)
-        {
-            return false;
+        // Solving p + t v = q + s w with Cramer's rule, t = t_num / det and s = s_num / det
+        let det = &vx * &wy - &vy * &wx;
+        let s_num = &dx * &vy - &dy * &vx;
+        if det.is_zero() {
+            return if s_num.is_zero() {
+                Crossing::Coincident
+            } else {
+                Crossing::Parallel
+            };
         }
-        // Verify if its even possible for Y's to intersect in area in the future
-        if (self.pos.1 < low && self.vel.1 < 0.0)
-            || (self.pos.1 > high && self.vel.1 > 0.0)
-            || (other.pos.1 < low && other.vel.1 < 0.0)
-            || (other.pos.1 > high && other.vel.1 > 0.0)
-        {
-            return false;
+        let t_num = &dx * &wy - &dy * &wx;
+        let point = (
+            BigRational::new(px * &det + &t_num * vx, det.clone()),
+            BigRational::new(py * &det + &t_num * vy, det.clone()),
+        );
+        // t or s is negative when its numerator has the opposite sign to `det`
+        if t_num.sign() == -det.sign() || s_num.sign() == -det.sign() {
+            Crossing::Past(point.0, point.1)
+        } else {
+            Crossing::Future(point.0, point.1)
         }
+    }
+}
+
+/// How the paths of two hailstones meet in x and y.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Crossing {
+    /// Both hailstones will pass through this point, now or later.
+    Future(BigRational, BigRational),
+    /// The paths cross here, but at least one hailstone already went by.
+    Past(BigRational, BigRational),
+    /// The paths never meet.
+    Parallel,
+    /// Both hailstones move along the same line.
+    Coincident,
+}
 
-        let p_intersect = self.intersection_xy(other);
+/// How every pair of hailstones crosses, relative to a test area.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Report {
+    /// Future crossings inside the test area, the part 1 answer.
+    pub inside: usize,
+    /// Future crossings outside it.
+    pub outside: usize,
+    pub past: usize,
+    pub parallel: usize,
+    pub coincident: usize,
+}
 
-        if let Some(point) = p_intersect {
-            point.0 > low && point.0 < high && point.1 > low && point.1 < high
-        } else {
-            false
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} inside, {} outside, {} in the past, {} parallel, {} coincident",
+            self.inside, self.outside, self.past, self.parallel, self.coincident
+        )
+    }
+}
+
+/// Classify every pair, counting future crossings with `x` and `y` both within the bounds
+/// (inclusive) as inside.
+pub fn crossing_report(input: &Input, x: RangeInclusive<i64>, y: RangeInclusive<i64>) -> Report {
+    let bound = |n: i64| BigRational::from_integer(n.into());
+    let (xlo, xhi) = (bound(*x.start()), bound(*x.end()));
+    let (ylo, yhi) = (bound(*y.start()), bound(*y.end()));
+    let mut report = Report::default();
+    for (i, flake1) in input.iter().enumerate() {
+        for flake2 in &input[i + 1..] {
+            match flake1.crossing_xy(flake2) {
+                Crossing::Future(px, py) => {
+                    if xlo <= px && px <= xhi && ylo <= py && py <= yhi {
+                        report.inside += 1;
+                    } else {
+                        report.outside += 1;
+                    }
+                }
+                Crossing::Past(..) => report.past += 1,
+                Crossing::Parallel => report.parallel += 1,
+                Crossing::Coincident => report.coincident += 1,
+            }
         }
     }
+    report
 }
 
 #[aoc_generator(day24)]
-pub fn load_input(input: &str) -> Input {
+pub fn load_input(input: &str) -> Result<Input, ParseError> {
     let mut flakes = vec![];
-    for line in input.lines() {
-        let mut temp = line.split('@');
-        let pos: Vec<f64> = temp
-            .next()
-            .unwrap()
-            .split(',')
-            .map(|s| s.trim().parse::<f64>().unwrap())
-            .collect();
-        let pos = (pos[0], pos[1], pos[2]);
-        let vel: Vec<f64> = temp
-            .next()
-            .unwrap()
-            .split(',')
-            .map(|s| s.trim().parse::<f64>().unwrap())
-            .collect();
-        let vel = (vel[0], vel[1], vel[2]);
+    let mut lines = vec![];
+    for line in parse::lines(24, input) {
+        let (pos, vel) = line.split_once(line.text, "@")?;
+        let pos = match line.list::<i64>(pos, ',')?[..] {
+            [x, y, z] => (x, y, z),
+            _ => return Err(line.error(pos, "`x, y, z`")),
+        };
+        let vel = match line.list::<i64>(vel, ',')?[..] {
+            [x, y, z] => (x, y, z),
+            _ => return Err(line.error(vel, "`x, y, z`")),
+        };
         flakes.push(Snowflake::new(pos, vel));
+        lines.push(line);
+    }
+    // Part 2 needs three to line the rock up with
+    if flakes.len() < 3 {
+        let expected = "at least three hailstones";
+        return Err(ParseError::new(24, flakes.len() + 1, 1, expected, ""));
+    }
+    // Line up the rock now, so part 2 can't fail
+    if let Err(e) = throw_sum(&flakes) {
+        let line = match e {
+            ThrowError::Misses(i) => lines[i],
+            _ => lines[0],
+        };
+        return Err(line.error(line.text, &format!("hailstones one rock can hit ({})", e)));
     }
-    flakes
+    Ok(flakes)
 }
 
-pub fn solve_part1(input: &Input, low: f64, high: f64) -> usize {
-    let n_flakes = input.len();
-    let mut output = 0;
-    for i in 0..n_flakes {
-        let flake1 = input[i];
-        for flake2 in &input[i + 1..] {
-            if flake1.intersect_in_bounds(flake2, low, high) {
-                output += 1;
+pub fn solve_part1(input: &Input, low: i64, high: i64) -> usize {
+    let report = crossing_report(input, low..=high, low..=high);
+    debug!(%report);
+    report.inside
+}
+
+#[aoc(day24, part1)]
+pub fn part1(input: &Input) -> usize {
+    solve_part1(input, 200000000000000, 400000000000000)
+}
+
+type Vector = [BigRational; 3];
+
+fn vector((x, y, z): (i64, i64, i64)) -> Vector {
+    [x, y, z].map(|n| BigRational::from_integer(n.into()))
+}
+
+fn sub(a: &Vector, b: &Vector) -> Vector {
+    [&a[0] - &b[0], &a[1] - &b[1], &a[2] - &b[2]]
+}
+
+fn cross(a: &Vector, b: &Vector) -> Vector {
+    [
+        &a[1] * &b[2] - &a[2] * &b[1],
+        &a[2] * &b[0] - &a[0] * &b[2],
+        &a[0] * &b[1] - &a[1] * &b[0],
+    ]
+}
+
+/// Where to throw the rock from and how fast, so it hits every hailstone.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Throw {
+    pub pos: Vector,
+    pub vel: Vector,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ThrowError {
+    /// Fewer than three hailstones, so the throw isn't pinned down.
+    TooFew,
+    /// No three hailstones give independent equations.
+    Degenerate,
+    /// The throw from the first three independent hailstones misses this one.
+    Misses(usize),
+    /// The throw starts between whole numbers.
+    Fractional,
+    /// The starting coordinates add up to more than an `i128` holds.
+    TooFar,
+}
+
+impl fmt::Display for ThrowError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ThrowError::TooFew => write!(f, "need at least three hailstones"),
+            ThrowError::Degenerate => write!(f, "the hailstones don't pin down a single throw"),
+            ThrowError::Misses(i) => write!(f, "no single throw hits hailstone {}", i),
+            ThrowError::Fractional => write!(f, "the rock starts between whole numbers"),
+            ThrowError::TooFar => write!(f, "the rock starts too far away for an i128"),
+        }
+    }
+}
+
+// Gauss-Jordan elimination on `[A | b]`, or `None` if `A` is singular
+fn solve_linear(mut rows: Vec<Vec<BigRational>>) -> Option<Vec<BigRational>> {
+    let n = rows.len();
+    for col in 0..n {
+        let pivot = (col..n).find(|&r| !rows[r][col].is_zero())?;
+        rows.swap(col, pivot);
+        let p = rows[col][col].clone();
+        for x in rows[col].iter_mut() {
+            *x /= &p;
+        }
+        for r in 0..n {
+            if r != col && !rows[r][col].is_zero() {
+                let factor = rows[r][col].clone();
+                let pivot_row = rows[col].clone();
+                for (x, y) in rows[r].iter_mut().zip(pivot_row) {
+                    *x -= &factor * y;
+                }
             }
         }
     }
-    output
+    Some(rows.into_iter().map(|row| row[n].clone()).collect())
 }
 
-#[aoc(day24, part1)]
-pub fn part1(input: &Input) -> usize {
-    solve_part1(input, 200000000000000.0, 400000000000000.0)
+/// The throw that hits all three hailstones, or `None` if they don't determine one.
+///
+/// Hitting hailstone `i` at some time means `P - p_i` is parallel to `V - v_i`, i.e.
+/// `(P - p_i) x (V - v_i) = 0`. Every one of those has the same non-linear `P x V` term, so
+/// subtracting them pairwise leaves `P x (v_j - v_i) + (p_j - p_i) x V = p_j x v_j - p_i x v_i`,
+/// which is linear. Two pairs give six equations for the six unknowns.
+pub fn throw_from(stones: [&Snowflake; 3]) -> Option<Throw> {
+    let p = stones.map(|s| vector(s.pos));
+    let v = stones.map(|s| vector(s.vel));
+    let mut rows = vec![];
+    for j in [1, 2] {
+        let a = sub(&v[j], &v[0]);
+        let b = sub(&p[j], &p[0]);
+        let c = sub(&cross(&p[j], &v[j]), &cross(&p[0], &v[0]));
+        // Coefficients of (Px, Py, Pz) in P x a, then of (Vx, Vy, Vz) in b x V
+        let zero = BigRational::zero;
+        let equations = [
+            [zero(), a[2].clone(), -&a[1], zero(), -&b[2], b[1].clone()],
+            [-&a[2], zero(), a[0].clone(), b[2].clone(), zero(), -&b[0]],
+            [a[1].clone(), -&a[0], zero(), -&b[1], b[0].clone(), zero()],
+        ];
+        for (equation, rhs) in equations.into_iter().zip(c) {
+            let mut row = equation.to_vec();
+            row.push(rhs);
+            rows.push(row);
+        }
+    }
+    let solution = solve_linear(rows)?;
+    Some(Throw {
+        pos: [0, 1, 2].map(|i| solution[i].clone()),
+        vel: [3, 4, 5].map(|i| solution[i].clone()),
+    })
+}
+
+impl Throw {
+    /// Whether the rock meets `stone` at the same place at the same time, now or later.
+    pub fn hits(&self, stone: &Snowflake) -> bool {
+        let gap = sub(&vector(stone.pos), &self.pos);
+        let closing = sub(&self.vel, &vector(stone.vel));
+        if !cross(&gap, &closing).iter().all(Zero::is_zero) {
+            return false;
+        }
+        // Parallel, so the collision time is the same on any axis that's moving
+        match (0..3).find(|&i| !closing[i].is_zero()) {
+            Some(i) => !(&gap[i] / &closing[i]).is_negative(),
+            None => gap.iter().all(Zero::is_zero),
+        }
+    }
+}
+
+/// The throw that hits every hailstone, worked out from the first three that are independent and
+/// checked against the rest.
+pub fn find_throw(input: &Input) -> Result<Throw, ThrowError> {
+    if input.len() < 3 {
+        return Err(ThrowError::TooFew);
+    }
+    let n = input.len();
+    let throw = (0..n)
+        .flat_map(|i| (i + 1..n).flat_map(move |j| (j + 1..n).map(move |k| (i, j, k))))
+        .find_map(|(i, j, k)| throw_from([&input[i], &input[j], &input[k]]))
+        .ok_or(ThrowError::Degenerate)?;
+    match input.iter().position(|stone| !throw.hits(stone)) {
+        Some(i) => Err(ThrowError::Misses(i)),
+        None => Ok(throw),
+    }
+}
+
+/// The sum of the coordinates the rock is thrown from.
+pub fn throw_sum(input: &Input) -> Result<i128, ThrowError> {
+    let throw = find_throw(input)?;
+    let sum = &throw.pos[0] + &throw.pos[1] + &throw.pos[2];
+    if !sum.is_integer() {
+        return Err(ThrowError::Fractional);
+    }
+    sum.to_integer().to_i128().ok_or(ThrowError::TooFar)
 }
 
 #[aoc(day24, part2)]
-pub fn part2(_input: &Input) -> i64 {
-    // Solve this stupid system of equations with sympy, see `day24.py`
-    808107741406756
+pub fn part2(input: &Input) -> i128 {
+    throw_sum(input).expect("load_input checks the rock can be thrown")
+}
+
+pub struct Day24;
+
+impl Solution for Day24 {
+    type Input = Input;
+
+    const DAY: u32 = 24;
+
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        load_input(input)
+    }
+
+    fn part1(input: &Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Input) -> Answer {
+        part2(input).into()
+    }
 }
 
 #[cfg(test)]
@@ -122,7 +340,59 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2023/24a.txt").unwrap();
-        let input = load_input(&input);
-        assert_eq!(solve_part1(&input, 7_f64, 27_f64), 2);
+        let input = load_input(&input).unwrap();
+        assert_eq!(solve_part1(&input, 7, 27), 2);
+
+        let report = crossing_report(&input, 7..=27, 7..=27);
+        let expected = Report {
+            inside: 2,
+            outside: 3,
+            past: 4,
+            parallel: 1,
+            coincident: 0,
+        };
+        assert_eq!(report, expected);
+        // Everything in the future is somewhere
+        let everywhere = crossing_report(&input, i64::MIN..=i64::MAX, i64::MIN..=i64::MAX);
+        assert_eq!(everywhere.inside, 5);
+
+        let third = |n: i64| BigRational::new(n.into(), 3.into());
+        assert_eq!(
+            input[0].crossing_xy(&input[1]),
+            Crossing::Future(third(43), third(46))
+        );
+        assert_eq!(input[1].crossing_xy(&input[2]), Crossing::Parallel);
+        let along = Snowflake::new((21, 12, 0), (-4, 2, 0));
+        assert_eq!(input[0].crossing_xy(&along), Crossing::Coincident);
+    }
+
+    #[test]
+    fn test_part2() {
+        let input = read_to_string("input/2023/24a.txt").unwrap();
+        let input = load_input(&input).unwrap();
+        assert_eq!(part2(&input), 47);
+        let throw = find_throw(&input).unwrap();
+        assert_eq!(throw.vel, vector((-3, 1, 2)));
+
+        let mut missed = input.clone();
+        missed.push(Snowflake::new((0, 0, 0), (1, 1, 1)));
+        assert_eq!(find_throw(&missed), Err(ThrowError::Misses(5)));
+
+        let stuck = vec![input[0]; 4];
+        assert_eq!(find_throw(&stuck), Err(ThrowError::Degenerate));
+        // The first three don't pin the throw down, but the first, second and fourth do
+        let spread = vec![input[0], input[1], input[1], input[2]];
+        assert_eq!(find_throw(&spread), Ok(throw.clone()));
+        let text = read_to_string("input/2023/24a.txt").unwrap();
+        let err = load_input(&format!("{}0, 0, 0 @ 1, 1, 1\n", text)).unwrap_err();
+        assert_eq!((err.line, err.column), (6, 1));
+        assert!(err.expected.contains("hailstone 5"));
+        let first = text.lines().next().unwrap();
+        assert!(load_input(&format!("{0}\n{0}\n{0}\n", first)).is_err());
+        assert_eq!(find_throw(&input[..2].to_vec()), Err(ThrowError::TooFew));
+        let err = load_input("").unwrap_err();
+        assert_eq!(err.expected, "at least three hailstones");
+        let err = load_input("19, 13, 30 @ -2, 1, -2\n18, 19, 22 @ -1, -1, -2").unwrap_err();
+        assert_eq!(err.line, 3);
     }
 }
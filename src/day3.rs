@@ -1,44 +1,35 @@
+use crate::parse::{self, ParseError};
+use crate::solution::{Answer, Solution};
 // Vector of Rows of (numbers, symbols)
 type Input = Vec<(Vec<(i64, i64)>, Vec<(i64, char)>)>;
 
 #[aoc_generator(day3)]
-pub fn load_input(input: &str) -> Input {
+pub fn load_input(input: &str) -> Result<Input, ParseError> {
     let mut output = vec![];
-    for line in input.lines() {
+    for line in parse::lines(3, input) {
         let mut numbers = vec![];
         let mut symbols = vec![];
-        let mut current_number = String::new();
-        let mut in_number = false;
-        for (i, c) in line.chars().enumerate() {
+        let mut number_start = None;
+        for (i, c) in line.text.char_indices() {
             if c.is_ascii_digit() {
-                current_number.push(c);
-                in_number = true;
-            } else if in_number {
-                numbers.push((
-                    i as i64 - current_number.len() as i64,
-                    current_number.parse::<i64>().unwrap(),
-                ));
-                in_number = false;
-                current_number = String::new();
-            }
-
-            if i == line.len() - 1 && in_number {
-                numbers.push((
-                    (i + 1) as i64 - current_number.len() as i64,
-                    current_number.parse::<i64>().unwrap(),
-                ));
-                in_number = false;
-                current_number = String::new();
+                number_start.get_or_insert(i);
+            } else if let Some(start) = number_start.take() {
+                let number = &line.text[start..i];
+                numbers.push((start as i64, line.parse::<i64>(number, "a number")?));
             }
 
             if !c.is_ascii_digit() && c != '.' {
                 symbols.push((i as i64, c));
             }
         }
+        if let Some(start) = number_start {
+            let number = &line.text[start..];
+            numbers.push((start as i64, line.parse::<i64>(number, "a number")?));
+        }
 
         output.push((numbers, symbols));
     }
-    output
+    Ok(output)
 }
 
 #[aoc(day3, part1)]
@@ -169,6 +160,26 @@ pub fn part2(input: &Input) -> i64 {
     ratios.iter().sum::<i64>()
 }
 
+pub struct Day3;
+
+impl Solution for Day3 {
+    type Input = Input;
+
+    const DAY: u32 = 3;
+
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        load_input(input)
+    }
+
+    fn part1(input: &Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Input) -> Answer {
+        part2(input).into()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -177,14 +188,14 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2023/03a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 4361);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2023/03a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 467835);
     }
 }
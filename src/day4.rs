@@ -1,30 +1,18 @@
+use crate::parse::{self, ParseError};
+use crate::solution::{Answer, Solution};
 // Vector of Rows of (numbers, symbols)
 type Input = Vec<(Vec<i64>, Vec<i64>)>;
 
 #[aoc_generator(day4)]
-pub fn load_input(input: &str) -> Input {
+pub fn load_input(input: &str) -> Result<Input, ParseError> {
     let mut output = vec![];
-    for line in input.lines() {
-        let mut liter = line.split(':').nth(1).unwrap().split('|');
-        let winning = liter.next().unwrap();
-        let mine = liter.next().unwrap();
-        let mut _winning = vec![];
-        for x in winning.split(' ') {
-            if let Ok(num) = x.parse::<i64>() {
-                _winning.push(num);
-            }
-        }
-        let mut _mine = vec![];
-        for x in mine.split(' ') {
-            if let Ok(num) = x.parse::<i64>() {
-                _mine.push(num);
-            }
-        }
-
-        output.push((_winning, _mine));
+    for line in parse::lines(4, input) {
+        let (_, numbers) = line.split_once(line.text, ":")?;
+        let (winning, mine) = line.split_once(numbers, "|")?;
+        output.push((line.numbers(winning)?, line.numbers(mine)?));
     }
 
-    output
+    Ok(output)
 }
 
 #[aoc(day4, part1)]
@@ -62,6 +50,26 @@ pub fn part2(input: &Input) -> i64 {
     copies.iter().sum::<i64>()
 }
 
+pub struct Day4;
+
+impl Solution for Day4 {
+    type Input = Input;
+
+    const DAY: u32 = 4;
+
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        load_input(input)
+    }
+
+    fn part1(input: &Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Input) -> Answer {
+        part2(input).into()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -70,14 +78,14 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2023/04a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 13);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2023/04a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 30);
     }
 }
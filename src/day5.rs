@@ -1,163 +1,308 @@
-// Vector of Rows of (numbers, symbols)
-type Input = (Vec<i64>, Vec<Vec<(i64, i64, i64)>>);
-
-fn parse_map(input: &str) -> Vec<(i64, i64, i64)> {
-    let mut output = vec![];
-    for line in input.lines() {
-        if line.is_empty() {
-            break;
+use crate::parse::{self, Line, ParseError};
+use crate::solution::{Answer, Solution};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use tracing::debug;
+
+pub type Input = Almanac;
+
+// `(dst, src, range)` for each line of a map
+type Rules = Vec<(i64, i64, i64)>;
+
+fn parse_map(lines: &[Line]) -> Result<Rules, ParseError> {
+    let mut output: Rules = vec![];
+    for line in lines {
+        match line.numbers::<i64>(line.text)?[..] {
+            [dst, src, range] => {
+                // Overlapping sources would make the map ambiguous
+                if output
+                    .iter()
+                    .any(|&(_, s, r)| src < s + r && s < src + range)
+                {
+                    return Err(line.error(line.text, "a source range not overlapping another"));
+                }
+                output.push((dst, src, range))
+            }
+            _ => return Err(line.error(line.text, "`<dst> <src> <range>`")),
         }
-        let temp: Vec<_> = line.split(' ').map(|x| x.parse::<i64>().unwrap()).collect();
-        let dst = temp[0];
-        let src = temp[1];
-        let range = temp[2];
-        output.push((dst, src, range));
     }
-    output
+    Ok(output)
+}
+
+/// The seeds and a chain of conversions from `seed` onwards, in the order the headers link them
+/// up rather than the order they're written in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Almanac {
+    pub seeds: Vec<i64>,
+    categories: Vec<String>,
+    // `layers[i]` converts `categories[i]` to `categories[i + 1]`
+    layers: Vec<Piecewise>,
 }
 
 #[aoc_generator(day5)]
-pub fn load_input(input: &str) -> Input {
-    let mut output = vec![];
-
-    let seeds_line = input.split('\n').next().unwrap();
-    let seeds_line = seeds_line.split(':').nth(1).unwrap();
-    let seeds: Vec<_> = seeds_line
-        .split(' ')
-        .skip(1)
-        .map(|x| x.parse::<i64>().unwrap())
-        .collect();
-
-    let sections = input.split(':');
-    let asdf = sections.clone().nth(2).unwrap();
-    let asdf = asdf.trim_start();
-    let seed_to_soil: Vec<(i64, i64, i64)> = parse_map(asdf);
-    output.push(seed_to_soil);
-
-    let asdf = sections.clone().nth(3).unwrap();
-    let asdf = asdf.trim_start();
-    let soil_to_fert = parse_map(asdf);
-    output.push(soil_to_fert);
-
-    let asdf = sections.clone().nth(4).unwrap();
-    let asdf = asdf.trim_start();
-    let fert_to_water = parse_map(asdf);
-    output.push(fert_to_water);
-
-    let asdf = sections.clone().nth(5).unwrap();
-    let asdf = asdf.trim_start();
-    let water_to_light = parse_map(asdf);
-    output.push(water_to_light);
-
-    let asdf = sections.clone().nth(6).unwrap();
-    let asdf = asdf.trim_start();
-    let light_to_temp = parse_map(asdf);
-    output.push(light_to_temp);
-
-    let asdf = sections.clone().nth(7).unwrap();
-    let asdf = asdf.trim_start();
-    let temp_to_humid = parse_map(asdf);
-    output.push(temp_to_humid);
-
-    let asdf = sections.clone().nth(8).unwrap();
-    let asdf = asdf.trim_start();
-    let humid_to_loc = parse_map(asdf);
-    output.push(humid_to_loc);
-
-    (seeds, output)
+pub fn load_input(input: &str) -> Result<Input, ParseError> {
+    let blocks = parse::blocks(5, input);
+    let Some(seeds_block) = blocks.first() else {
+        return Err(ParseError::new(5, 1, 1, "a `seeds:` line", ""));
+    };
+    let line = seeds_block[0];
+    let rest = line.strip_prefix(line.text, "seeds:")?;
+    let seeds: Vec<i64> = line.numbers(rest)?;
+    // Part 2 reads the seeds as `<start> <length>` pairs
+    let tokens: Vec<&str> = rest.split_whitespace().collect();
+    if seeds.is_empty() || seeds.len() % 2 == 1 {
+        return Err(line.error_after(line.text, "a `<start> <length>` pair of seeds"));
+    }
+    for (i, pair) in seeds.chunks(2).enumerate() {
+        if pair[1] < 1 {
+            return Err(line.error(tokens[2 * i + 1], "a length above zero"));
+        }
+        if pair[0].checked_add(pair[1]).is_none() {
+            return Err(line.error(tokens[2 * i + 1], "a range that fits in an i64"));
+        }
+    }
+
+    // Every other block is a `<from>-to-<to> map:` header followed by the map itself. Each
+    // category can only be converted from and to once, or there'd be more than one answer.
+    let mut conversions: HashMap<&str, (&str, Line, Rules)> = HashMap::new();
+    let mut targets = HashSet::new();
+    for block in &blocks[1..] {
+        let header = block[0];
+        let name = header.strip_suffix(header.text, " map:")?;
+        let (from, to) = header.split_once(name, "-to-")?;
+        if conversions.contains_key(from) {
+            return Err(header.error(from, "a category without another map out of it"));
+        }
+        if !targets.insert(to) {
+            return Err(header.error(to, "a category without another map into it"));
+        }
+        conversions.insert(from, (to, header, parse_map(&block[1..])?));
+    }
+
+    // Follow the headers from `seed`
+    let mut categories = vec!["seed".to_string()];
+    let mut layers = vec![];
+    let mut current = "seed";
+    while let Some((to, header, rules)) = conversions.remove(current) {
+        if categories.iter().any(|c| c == to) {
+            return Err(header.error(to, "a category that isn't already in the chain"));
+        }
+        categories.push(to.to_string());
+        layers.push(Piecewise::from_rules(&rules));
+        current = to;
+    }
+    // Anything left over isn't connected to the seeds
+    if let Some((_, header, _)) = conversions.values().min_by_key(|(_, h, _)| h.number) {
+        let chained = format!(
+            "a map out of `{}`, where the chain from `seed` ends",
+            current
+        );
+        return Err(header.error(header.text, &chained));
+    }
+
+    Ok(Almanac {
+        seeds,
+        categories,
+        layers,
+    })
 }
 
-/// Propagates states from direction of Seeds -> Locations.
-fn forward(seed: i64, rules: &[Vec<(i64, i64, i64)>]) -> i64 {
-    let mut nseed = seed;
-
-    // Layers of conversions
-    for _conv in rules {
-        // For each tuple mapping in this layer
-        for conv in _conv {
-            if nseed >= conv.1 && nseed < conv.1 + conv.2 {
-                // contains mapping
-                let diff = nseed - conv.1;
-                nseed = conv.0 + diff;
-                break;
-            }
+impl Almanac {
+    /// From `seed` to the last category, usually `location`.
+    pub fn categories(&self) -> &[String] {
+        &self.categories
+    }
+
+    /// Every conversion from `seed` to the last category rolled into one.
+    pub fn chain(&self) -> Piecewise {
+        self.layers
+            .iter()
+            .fold(Piecewise::identity(), |map, layer| map.compose(layer))
+    }
+
+    /// All the conversions between two categories rolled into one. Going back up the chain needs
+    /// the maps on the way to be invertible. `None` if either category is unknown.
+    pub fn map(&self, from: &str, to: &str) -> Option<Piecewise> {
+        let index = |c: &str| self.categories.iter().position(|x| x == c);
+        let (i, j) = (index(from)?, index(to)?);
+        let forward = |i: usize, j: usize| {
+            self.layers[i..j]
+                .iter()
+                .fold(Piecewise::identity(), |map, layer| map.compose(layer))
+        };
+        if i <= j {
+            Some(forward(i, j))
+        } else {
+            forward(j, i).inverse()
         }
     }
-    nseed
+
+    /// `x` as a `from` converted to a `to`, e.g. `convert("soil", "humidity", 81)`.
+    pub fn convert(&self, from: &str, to: &str, x: i64) -> Option<i64> {
+        Some(self.map(from, to)?.apply(x))
+    }
 }
 
-/*
- *
-        let dst = temp[0];
-        let src = temp[1];
-        let range = temp[2];
-*/
-#[aoc(day5, part1)]
-pub fn part1(input: &Input) -> i64 {
-    let (seeds, rules) = input;
-    seeds.iter().map(|&x| forward(x, rules)).min().unwrap()
+/// A map from the integers to themselves made of shifted intervals, like an almanac layer.
+/// Stored as `(start, offset)` pairs sorted by start: `x` in `[start, next start)` goes to
+/// `x + offset`. The first piece starts at `i64::MIN` so every `x` is covered; `i64::MAX` is
+/// treated as the end of the line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Piecewise {
+    pieces: Vec<(i64, i64)>,
 }
 
-/// Propagates states from direction of Locations -> Seeds.
-fn backward(seed: i64, rev_rules: &[Vec<(i64, i64, i64)>]) -> i64 {
-    let mut nseed = seed;
-
-    // Layers of conversions
-    for _conv in rev_rules {
-        // For each tuple mapping in this layer
-        for conv in _conv {
-            if nseed >= conv.0 && nseed < conv.0 + conv.2 {
-                // contains mapping
-                let diff = nseed - conv.0;
-                nseed = conv.1 + diff;
-                break;
+impl Piecewise {
+    pub fn identity() -> Self {
+        Self {
+            pieces: vec![(i64::MIN, 0)],
+        }
+    }
+
+    /// One layer of `(dst, src, range)` rules. Anything outside every `src` range maps to itself.
+    /// The sources mustn't overlap.
+    pub fn from_rules(rules: &[(i64, i64, i64)]) -> Self {
+        let mut rules = rules.to_vec();
+        rules.sort_by_key(|&(_, src, _)| src);
+        let mut pieces = vec![(i64::MIN, 0)];
+        for (dst, src, range) in rules {
+            pieces.push((src, dst - src));
+            pieces.push((src + range, 0));
+        }
+        Self::normalized(pieces)
+    }
+
+    // Sort, drop empty pieces and merge neighbours with the same offset
+    fn normalized(mut pieces: Vec<(i64, i64)>) -> Self {
+        // Stable, so of several pieces starting together the last one wins below
+        pieces.sort_by_key(|&(start, _)| start);
+        let mut output: Vec<(i64, i64)> = vec![];
+        for (start, offset) in pieces {
+            match output.last_mut() {
+                Some(last) if last.0 == start => last.1 = offset,
+                _ => output.push((start, offset)),
+            }
+            if output.len() >= 2 && output[output.len() - 2].1 == output[output.len() - 1].1 {
+                output.pop();
+            }
+        }
+        Self { pieces: output }
+    }
+
+    // Each piece as `[start, end)` with its offset
+    fn intervals(&self) -> impl Iterator<Item = (i64, i64, i64)> + '_ {
+        self.pieces.iter().enumerate().map(|(i, &(start, offset))| {
+            let end = self.pieces.get(i + 1).map_or(i64::MAX, |p| p.0);
+            (start, end, offset)
+        })
+    }
+
+    pub fn apply(&self, x: i64) -> i64 {
+        let i = self.pieces.partition_point(|&(start, _)| start <= x) - 1;
+        x + self.pieces[i].1
+    }
+
+    /// This map followed by `then`.
+    pub fn compose(&self, then: &Piecewise) -> Self {
+        let mut pieces = vec![];
+        for (start, end, offset) in self.intervals() {
+            // Split our image wherever `then` changes offset
+            let (lo, hi) = (start.saturating_add(offset), end.saturating_add(offset));
+            for (s, e, o) in then.intervals() {
+                if lo.max(s) < hi.min(e) {
+                    pieces.push((lo.max(s) - offset, offset + o));
+                }
             }
         }
+        Self::normalized(pieces)
+    }
+
+    /// The map the other way round, or `None` if this isn't a bijection (two pieces land on top
+    /// of each other, which also leaves a hole somewhere else).
+    pub fn inverse(&self) -> Option<Self> {
+        let mut images: Vec<_> = self
+            .intervals()
+            .map(|(start, end, offset)| {
+                (
+                    start.saturating_add(offset),
+                    end.saturating_add(offset),
+                    -offset,
+                )
+            })
+            .collect();
+        images.sort_unstable();
+        let tiles = images[0].0 == i64::MIN
+            && images.last().unwrap().1 == i64::MAX
+            && images.windows(2).all(|w| w[0].1 == w[1].0);
+        tiles.then(|| Self::normalized(images.iter().map(|&(s, _, o)| (s, o)).collect()))
+    }
+
+    /// Where the values in `[start, end)` end up, as disjoint intervals in no particular order.
+    pub fn map_interval(&self, start: i64, end: i64) -> Vec<(i64, i64)> {
+        self.intervals()
+            .filter(|&(s, e, _)| s.max(start) < e.min(end))
+            .map(|(s, e, o)| (s.max(start) + o, e.min(end) + o))
+            .collect()
     }
-    nseed
+}
+
+impl fmt::Display for Piecewise {
+    /// One line per piece that actually moves anything.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (start, end, offset) in self.intervals().filter(|&(_, _, o)| o != 0) {
+            writeln!(
+                f,
+                "{}..{} -> {}..{} ({:+})",
+                start,
+                end,
+                start + offset,
+                end + offset,
+                offset
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[aoc(day5, part1)]
+pub fn part1(input: &Input) -> i64 {
+    let map = input.chain();
+    input.seeds.iter().map(|&x| map.apply(x)).min().unwrap()
 }
 
 #[aoc(day5, part2)]
 /// 46210551 is too low
 pub fn part2(input: &Input) -> i64 {
-    let (seeds, rules) = input;
-    let mut seed_pairs: Vec<(i64, i64)> = vec![];
-    let mut i = 0;
-    while i < seeds.len() {
-        seed_pairs.push((seeds[i], seeds[i + 1]));
-        i += 2;
-    }
-
-    let rev_rules: Vec<_> = rules.iter().cloned().rev().collect();
-
-    // The min_seed is the first all-pass-through value which could work. We
-    // presume the intervals cover this value, but its worth checking.
-    let min_seed = seed_pairs.iter().map(|x| x.0).min().unwrap();
-    println!("min_seed: {}", min_seed);
-
-    let min_interval_value = rules
-        .iter()
-        .map(|_x| {
-            _x.iter()
-                .map(|x| if x.0 < x.1 { x.0 } else { x.1 })
-                .min()
-                .unwrap()
-        })
+    let map = input.chain();
+    debug!("seed to location:\n{}", map);
+
+    // Push each range of seeds through as a whole
+    input
+        .seeds
+        .chunks(2)
+        .flat_map(|pair| map.map_interval(pair[0], pair[0] + pair[1]))
+        .map(|(start, _)| start)
         .min()
-        .unwrap();
-    println!("min_interval_value: {}", min_interval_value);
-
-    let mut loc = 0;
-    loop {
-        let seed = backward(loc, &rev_rules);
-        for pair in &seed_pairs {
-            if seed >= pair.0 && seed < pair.0 + pair.1 {
-                return loc;
-            }
-        }
+        .unwrap()
+}
+
+pub struct Day5;
+
+impl Solution for Day5 {
+    type Input = Input;
 
-        // This loc doesn't map back to a valid seed, try next
-        loc += 1;
+    const DAY: u32 = 5;
+
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        load_input(input)
+    }
+
+    fn part1(input: &Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Input) -> Answer {
+        part2(input).into()
     }
 }
 
@@ -169,14 +314,75 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2023/05a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 35);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2023/05a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 46);
     }
+
+    #[test]
+    fn test_piecewise() {
+        let input = read_to_string("input/2023/05a.txt").unwrap();
+        let almanac = load_input(&input).unwrap();
+        let map = almanac.map("seed", "location").unwrap();
+        let locations: Vec<_> = almanac.seeds.iter().map(|&s| map.apply(s)).collect();
+        assert_eq!(locations, vec![82, 43, 86, 35]);
+
+        let inverse = map.inverse().unwrap();
+        for x in -5..200 {
+            assert_eq!(inverse.apply(map.apply(x)), x);
+        }
+        assert_eq!(map.compose(&inverse), Piecewise::identity());
+
+        // Both 0..10 and 10..20 land on 5..15
+        let squashed = Piecewise::from_rules(&[(5, 0, 10), (5, 10, 10)]);
+        assert_eq!(squashed.inverse(), None);
+        let mut pieces = squashed.map_interval(8, 12);
+        pieces.sort();
+        assert_eq!(pieces, vec![(5, 7), (13, 15)]);
+    }
+
+    #[test]
+    fn test_almanac() {
+        let input = read_to_string("input/2023/05a.txt").unwrap();
+        let almanac = load_input(&input).unwrap();
+        assert_eq!(almanac.categories().last().unwrap(), "location");
+        assert_eq!(almanac.convert("soil", "humidity", 81), Some(78));
+        assert_eq!(almanac.convert("light", "seed", 74), Some(79));
+        assert_eq!(almanac.convert("seed", "nowhere", 79), None);
+
+        // Order of the blocks doesn't matter, only the headers
+        let blocks: Vec<&str> = input.split("\n\n").collect();
+        let mut shuffled = blocks.clone();
+        shuffled[1..].reverse();
+        assert_eq!(load_input(&shuffled.join("\n\n")).unwrap(), almanac);
+
+        let broken = input.replace("soil-to-fertilizer", "soil-to-manure");
+        let err = load_input(&broken).unwrap_err();
+        assert_eq!((err.line, err.column), (12, 1));
+
+        let ambiguous = input.replace("fertilizer-to-water", "soil-to-water");
+        let err = load_input(&ambiguous).unwrap_err();
+        assert_eq!((err.line, err.column), (12, 1));
+
+        // The chain doesn't have to end at `location`
+        let short = "seeds: 79 14 55 13\n\nseed-to-soil map:\n50 98 2\n52 50 48\n";
+        let almanac = load_input(short).unwrap();
+        assert_eq!((part1(&almanac), part2(&almanac)), (13, 57));
+        assert_eq!(almanac.chain(), almanac.map("seed", "soil").unwrap());
+        let seeds_only = load_input("seeds: 7 3").unwrap();
+        assert_eq!((part1(&seeds_only), part2(&seeds_only)), (3, 7));
+
+        let err = load_input("seeds: 79 14 55").unwrap_err();
+        assert_eq!(err.column, 16);
+        assert!(load_input("seeds:").is_err());
+        let err = load_input("seeds: 79 0").unwrap_err();
+        assert_eq!(err.column, 11);
+        assert!(load_input(&format!("seeds: {} 2", i64::MAX)).is_err());
+    }
 }
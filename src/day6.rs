@@ -1,20 +1,19 @@
+use crate::parse::{self, ParseError};
+use crate::solution::{Answer, Solution};
 // Vector of Rows of (numbers, symbols)
 type Input = Vec<(usize, usize)>;
 
 #[aoc_generator(day6)]
-pub fn load_input(input: &str) -> Input {
-    let lines_arr: Vec<_> = input.lines().collect();
-    let times: Vec<_> = lines_arr[0]
-        .split_ascii_whitespace()
-        .skip(1)
-        .map(|x| x.parse::<usize>().unwrap())
-        .collect();
-    let dists: Vec<_> = lines_arr[1]
-        .split_ascii_whitespace()
-        .skip(1)
-        .map(|x| x.parse::<usize>().unwrap())
-        .collect();
-    times.into_iter().zip(dists).collect()
+pub fn load_input(input: &str) -> Result<Input, ParseError> {
+    let mut lines = parse::lines(6, input);
+    let line = lines.next_line("a `Time:` line")?;
+    let times: Vec<usize> = line.numbers(line.strip_prefix(line.text, "Time:")?)?;
+    let line = lines.next_line("a `Distance:` line")?;
+    let dists: Vec<usize> = line.numbers(line.strip_prefix(line.text, "Distance:")?)?;
+    if dists.len() != times.len() {
+        return Err(line.error_after(line.text, &format!("{} distances", times.len())));
+    }
+    Ok(times.into_iter().zip(dists).collect())
 }
 
 #[aoc(day6, part1)]
@@ -64,6 +63,26 @@ pub fn part2(input: &Input) -> i64 {
     (root1.ceil() as i64 - root2.ceil() as i64).abs()
 }
 
+pub struct Day6;
+
+impl Solution for Day6 {
+    type Input = Input;
+
+    const DAY: u32 = 6;
+
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        load_input(input)
+    }
+
+    fn part1(input: &Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Input) -> Answer {
+        part2(input).into()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -72,14 +91,14 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2023/06a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 288);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2023/06a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 71503);
     }
 }
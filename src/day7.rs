@@ -1,3 +1,5 @@
+use crate::parse::{self, ParseError};
+use crate::solution::{Answer, Solution};
 use std::cmp::Ordering;
 
 type Input = Vec<(Hand, i64)>;
@@ -55,9 +57,7 @@ fn first_greater(first: &str, second: &str, part2: bool) -> bool {
             _ => (),
         }
     }
-    println!("hand1: {}", first);
-    println!("hand2: {}", second);
-    panic!("hands equal!");
+    panic!("hands equal! {} {}", first, second);
 }
 
 // 7 - 5 of a kind
@@ -186,13 +186,17 @@ fn swap_jokers_work(hand: &str, idx: usize) -> Vec<String> {
 }
 
 #[aoc_generator(day7)]
-pub fn load_input(input: &str) -> Input {
+pub fn load_input(input: &str) -> Result<Input, ParseError> {
     let mut output = vec![];
-    for line in input.lines() {
-        let derp: Vec<_> = line.split(' ').collect();
-        output.push((Hand::new(derp[0]), derp[1].parse::<i64>().unwrap()));
+    for line in parse::lines(7, input) {
+        let (hand, bid) = line.split_once(line.text, " ")?;
+        let hand = line.only(hand, "AKQJT98765432")?;
+        if hand.len() != 5 {
+            return Err(line.error(hand, "5 cards"));
+        }
+        output.push((Hand::new(hand), line.parse::<i64>(bid, "a bid")?));
     }
-    output
+    Ok(output)
 }
 
 #[aoc(day7, part1)]
@@ -219,6 +223,26 @@ pub fn part2(input: &Input) -> i64 {
     output
 }
 
+pub struct Day7;
+
+impl Solution for Day7 {
+    type Input = Input;
+
+    const DAY: u32 = 7;
+
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        load_input(input)
+    }
+
+    fn part1(input: &Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Input) -> Answer {
+        part2(input).into()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -227,14 +251,14 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2023/07a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 6440);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2023/07a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 5905);
     }
 }
@@ -1,25 +1,24 @@
+use crate::parse::{self, ParseError};
+use crate::solution::{Answer, Solution};
 use num::Integer;
 use std::collections::HashMap;
 
 type Input = (Vec<char>, HashMap<String, (String, String)>);
 
 #[aoc_generator(day8)]
-pub fn load_input(input: &str) -> Input {
-    let instructions = input.lines().next().unwrap().chars().collect();
+pub fn load_input(input: &str) -> Result<Input, ParseError> {
+    let mut lines = parse::lines(8, input);
+    let line = lines.next_line("a line of instructions")?;
+    let instructions = line.only(line.text, "LR")?.chars().collect();
     let mut mapping = HashMap::new();
-    for line in input.lines().skip(2) {
-        let temp: Vec<_> = line.split(' ').collect();
-        let node = temp[0].to_string();
-        let left = temp[2]
-            .strip_prefix('(')
-            .unwrap()
-            .strip_suffix(',')
-            .unwrap()
-            .to_string();
-        let right = temp[3].strip_suffix(')').unwrap().to_string();
-        mapping.insert(node, (left, right));
+    for line in lines.skip(1) {
+        let (node, pair) = line.split_once(line.text, " = ")?;
+        let pair = line.strip_prefix(pair, "(")?;
+        let pair = line.strip_suffix(pair, ")")?;
+        let (left, right) = line.split_once(pair, ", ")?;
+        mapping.insert(node.to_string(), (left.to_string(), right.to_string()));
     }
-    (instructions, mapping)
+    Ok((instructions, mapping))
 }
 
 #[aoc(day8, part1)]
@@ -105,6 +104,26 @@ pub fn part2(input: &Input) -> usize {
     lcm05
 }
 
+pub struct Day8;
+
+impl Solution for Day8 {
+    type Input = Input;
+
+    const DAY: u32 = 8;
+
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        load_input(input)
+    }
+
+    fn part1(input: &Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Input) -> Answer {
+        part2(input).into()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -113,14 +132,14 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2023/08a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 6);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2023/08b.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 6);
     }
 }
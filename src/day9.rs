@@ -1,13 +1,12 @@
+use crate::parse::{self, ParseError};
+use crate::solution::{Answer, Solution};
 type Input = Vec<Vec<i64>>;
 
 #[aoc_generator(day9)]
-pub fn load_input(input: &str) -> Input {
-    let mut output = vec![];
-    for line in input.lines() {
-        let temp = line.split(' ').map(|x| x.parse::<i64>().unwrap()).collect();
-        output.push(temp);
-    }
-    output
+pub fn load_input(input: &str) -> Result<Input, ParseError> {
+    parse::lines(9, input)
+        .map(|line| line.numbers(line.text))
+        .collect()
 }
 
 fn diff(x: &[i64]) -> Vec<i64> {
@@ -67,6 +66,26 @@ pub fn part2(input: &Input) -> i64 {
     output.iter().sum::<i64>()
 }
 
+pub struct Day9;
+
+impl Solution for Day9 {
+    type Input = Input;
+
+    const DAY: u32 = 9;
+
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        load_input(input)
+    }
+
+    fn part1(input: &Input) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Input) -> Answer {
+        part2(input).into()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -75,14 +94,14 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2023/09a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 114);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2023/09a.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 2);
     }
 }
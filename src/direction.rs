@@ -0,0 +1,214 @@
+/// One of the four grid headings. Like `Grid`, y grows downwards so `North` is `(0, -1)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    /// Clockwise from `North`.
+    pub const ALL: [Direction; 4] = [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ];
+
+    pub fn turn_left(self) -> Self {
+        match self {
+            Direction::North => Direction::West,
+            Direction::East => Direction::North,
+            Direction::South => Direction::East,
+            Direction::West => Direction::South,
+        }
+    }
+
+    pub fn turn_right(self) -> Self {
+        match self {
+            Direction::North => Direction::East,
+            Direction::East => Direction::South,
+            Direction::South => Direction::West,
+            Direction::West => Direction::North,
+        }
+    }
+
+    pub fn reverse(self) -> Self {
+        match self {
+            Direction::North => Direction::South,
+            Direction::East => Direction::West,
+            Direction::South => Direction::North,
+            Direction::West => Direction::East,
+        }
+    }
+
+    pub fn is_vertical(self) -> bool {
+        self == Direction::North || self == Direction::South
+    }
+
+    pub fn delta(self) -> (i64, i64) {
+        match self {
+            Direction::North => (0, -1),
+            Direction::East => (1, 0),
+            Direction::South => (0, 1),
+            Direction::West => (-1, 0),
+        }
+    }
+
+    /// Move `n` steps from `pos` on an unbounded plane.
+    pub fn offset(self, pos: (i64, i64), n: i64) -> (i64, i64) {
+        let (dx, dy) = self.delta();
+        (pos.0 + dx * n, pos.1 + dy * n)
+    }
+
+    /// Move `n` steps from `pos`, or `None` if that would leave `[0, bounds.0) x [0, bounds.1)`.
+    /// Never overflows, no matter how big `n` is.
+    pub fn step(
+        self,
+        pos: (usize, usize),
+        n: usize,
+        bounds: (usize, usize),
+    ) -> Option<(usize, usize)> {
+        let (x, y) = pos;
+        let next = match self {
+            Direction::North => (x, y.checked_sub(n)?),
+            Direction::East => (x.checked_add(n)?, y),
+            Direction::South => (x, y.checked_add(n)?),
+            Direction::West => (x.checked_sub(n)?, y),
+        };
+        if next.0 < bounds.0 && next.1 < bounds.1 {
+            Some(next)
+        } else {
+            None
+        }
+    }
+}
+
+/// Accepts `U/D/L/R`, `N/E/S/W` and `^/v/</>`. The error is the offending char.
+impl TryFrom<char> for Direction {
+    type Error = char;
+
+    fn try_from(c: char) -> Result<Self, char> {
+        match c {
+            'U' | 'N' | '^' => Ok(Direction::North),
+            'R' | 'E' | '>' => Ok(Direction::East),
+            'D' | 'S' | 'v' => Ok(Direction::South),
+            'L' | 'W' | '<' => Ok(Direction::West),
+            _ => Err(c),
+        }
+    }
+}
+
+/// Eight-way heading, for when diagonals count too.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Compass {
+    N,
+    NE,
+    E,
+    SE,
+    S,
+    SW,
+    W,
+    NW,
+}
+
+impl Compass {
+    /// Clockwise from `N`.
+    pub const ALL: [Compass; 8] = [
+        Compass::N,
+        Compass::NE,
+        Compass::E,
+        Compass::SE,
+        Compass::S,
+        Compass::SW,
+        Compass::W,
+        Compass::NW,
+    ];
+
+    fn index(self) -> usize {
+        Compass::ALL.iter().position(|&c| c == self).unwrap()
+    }
+
+    /// Rotate 45 degrees counter-clockwise.
+    pub fn turn_left(self) -> Self {
+        Compass::ALL[(self.index() + 7) % 8]
+    }
+
+    /// Rotate 45 degrees clockwise.
+    pub fn turn_right(self) -> Self {
+        Compass::ALL[(self.index() + 1) % 8]
+    }
+
+    pub fn reverse(self) -> Self {
+        Compass::ALL[(self.index() + 4) % 8]
+    }
+
+    pub fn delta(self) -> (i64, i64) {
+        match self {
+            Compass::N => (0, -1),
+            Compass::NE => (1, -1),
+            Compass::E => (1, 0),
+            Compass::SE => (1, 1),
+            Compass::S => (0, 1),
+            Compass::SW => (-1, 1),
+            Compass::W => (-1, 0),
+            Compass::NW => (-1, -1),
+        }
+    }
+
+    pub fn offset(self, pos: (i64, i64), n: i64) -> (i64, i64) {
+        let (dx, dy) = self.delta();
+        (pos.0 + dx * n, pos.1 + dy * n)
+    }
+}
+
+impl From<Direction> for Compass {
+    fn from(dir: Direction) -> Self {
+        match dir {
+            Direction::North => Compass::N,
+            Direction::East => Compass::E,
+            Direction::South => Compass::S,
+            Direction::West => Compass::W,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_turns() {
+        for dir in Direction::ALL {
+            assert_eq!(dir.turn_left().turn_right(), dir);
+            assert_eq!(dir.turn_right().turn_right(), dir.reverse());
+            assert_eq!(Compass::from(dir).reverse(), Compass::from(dir.reverse()));
+        }
+        assert_eq!(Compass::NW.turn_right(), Compass::N);
+        assert_eq!(Compass::N.turn_left(), Compass::NW);
+    }
+
+    #[test]
+    fn test_step() {
+        assert_eq!(Direction::North.step((3, 2), 2, (5, 5)), Some((3, 0)));
+        assert_eq!(Direction::North.step((3, 2), 3, (5, 5)), None);
+        assert_eq!(Direction::East.step((3, 2), 2, (5, 5)), None);
+        assert_eq!(Direction::East.step((3, 2), usize::MAX, (5, 5)), None);
+        assert_eq!(Direction::West.offset((0, 0), 4), (-4, 0));
+    }
+
+    #[test]
+    fn test_parse() {
+        let parsed: Vec<_> = "URDL NESW ^>v<"
+            .split(' ')
+            .map(|s| {
+                s.chars()
+                    .map(|c| Direction::try_from(c).unwrap())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        assert!(parsed.iter().all(|p| *p == Direction::ALL));
+        assert_eq!(Direction::try_from('x'), Err('x'));
+    }
+}
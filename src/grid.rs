@@ -0,0 +1,322 @@
+use crate::direction::{Compass, Direction};
+use crate::parse::{self, Line, ParseError};
+use std::fmt;
+use std::ops::{Index, IndexMut};
+use std::str::FromStr;
+
+/// Dense, row-major 2D grid. Positions are `(x, y)` with `(0, 0)` in the top left corner, which
+/// is how every day reads its puzzle input.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+
+    /// Returns a copy of this grid with `n` cells of `fill` added on every side.
+    pub fn padded(&self, n: usize, fill: T) -> Self {
+        let mut output = Grid::new(self.width + 2 * n, self.height + 2 * n, fill);
+        for ((x, y), v) in self.iter() {
+            output[(x + n, y + n)] = v.clone();
+        }
+        output
+    }
+
+    pub fn transpose(&self) -> Self {
+        let cells = (0..self.width)
+            .flat_map(|x| self.column(x).cloned())
+            .collect();
+        Self {
+            width: self.height,
+            height: self.width,
+            cells,
+        }
+    }
+
+    /// Rotate 90 degrees clockwise.
+    pub fn rotate_cw(&self) -> Self {
+        let mut output = self.transpose();
+        output.flip_horizontal();
+        output
+    }
+
+    /// Rotate 90 degrees counter-clockwise.
+    pub fn rotate_ccw(&self) -> Self {
+        let mut output = self.transpose();
+        output.flip_vertical();
+        output
+    }
+}
+
+impl<T> Grid<T> {
+    /// Build a grid from a list of rows, all of which must be the same length.
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Self {
+        let height = rows.len();
+        let width = rows.first().map(|r| r.len()).unwrap_or(0);
+        assert!(
+            rows.iter().all(|r| r.len() == width),
+            "all grid rows must be the same length"
+        );
+        Self {
+            width,
+            height,
+            cells: rows.into_iter().flatten().collect(),
+        }
+    }
+
+    /// Parse one cell per char, one row per line. `f` returns `None` for chars it won't accept,
+    /// which is reported as `expected`.
+    pub fn parse_with<'a>(
+        lines: impl IntoIterator<Item = Line<'a>>,
+        expected: &str,
+        mut f: impl FnMut(char) -> Option<T>,
+    ) -> Result<Self, ParseError> {
+        let mut rows: Vec<Vec<T>> = vec![];
+        for line in lines {
+            let mut row = vec![];
+            for (i, c) in line.text.char_indices() {
+                row.push(f(c).ok_or_else(|| line.error(&line.text[i..], expected))?);
+            }
+
+            // Every row has to be as wide as the first
+            if let Some(width) = rows.first().map(|r| r.len()) {
+                let expected = format!("{} columns", width);
+                if row.len() > width {
+                    let (i, _) = line.text.char_indices().nth(width).unwrap();
+                    return Err(line.error(&line.text[i..], &expected));
+                } else if row.len() < width {
+                    return Err(line.error_after(line.text, &expected));
+                }
+            }
+            rows.push(row);
+        }
+        Ok(Self::from_rows(rows))
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn in_bounds(&self, pos: (i64, i64)) -> bool {
+        pos.0 >= 0 && pos.1 >= 0 && (pos.0 as usize) < self.width && (pos.1 as usize) < self.height
+    }
+
+    /// Bounds-checked lookup, takes signed coordinates so callers can probe off the edge.
+    pub fn get(&self, pos: (i64, i64)) -> Option<&T> {
+        if self.in_bounds(pos) {
+            Some(&self.cells[pos.1 as usize * self.width + pos.0 as usize])
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, pos: (i64, i64)) -> Option<&mut T> {
+        if self.in_bounds(pos) {
+            Some(&mut self.cells[pos.1 as usize * self.width + pos.0 as usize])
+        } else {
+            None
+        }
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn column(&self, x: usize) -> impl DoubleEndedIterator<Item = &T> + '_ {
+        (0..self.height).map(move |y| &self.cells[y * self.width + x])
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> + '_ {
+        (0..self.height).map(|y| self.row(y))
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> + '_ {
+        self.positions().zip(self.cells.iter())
+    }
+
+    /// Position of the first cell (in reading order) equal to `value`.
+    pub fn find(&self, value: &T) -> Option<(usize, usize)>
+    where
+        T: PartialEq,
+    {
+        self.iter().find(|(_, v)| *v == value).map(|(p, _)| p)
+    }
+
+    /// Move `n` steps from `pos`, or `None` if that would leave the grid.
+    pub fn step(&self, pos: (usize, usize), dir: Direction, n: usize) -> Option<(usize, usize)> {
+        dir.step(pos, n, (self.width, self.height))
+    }
+
+    /// In-bounds orthogonal neighbours: north, east, south, west.
+    pub fn neighbors4(&self, pos: (usize, usize)) -> impl Iterator<Item = (usize, usize)> + '_ {
+        Direction::ALL
+            .into_iter()
+            .filter_map(move |dir| self.step(pos, dir, 1))
+    }
+
+    /// In-bounds orthogonal and diagonal neighbours, clockwise from north.
+    pub fn neighbors8(&self, pos: (usize, usize)) -> impl Iterator<Item = (usize, usize)> + '_ {
+        Compass::ALL.into_iter().filter_map(move |dir| {
+            let p = dir.offset((pos.0 as i64, pos.1 as i64), 1);
+            if self.in_bounds(p) {
+                Some((p.0 as usize, p.1 as usize))
+            } else {
+                None
+            }
+        })
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    /// Mirror left to right.
+    pub fn flip_horizontal(&mut self) {
+        for row in self.cells.chunks_mut(self.width.max(1)) {
+            row.reverse();
+        }
+    }
+
+    /// Mirror top to bottom.
+    pub fn flip_vertical(&mut self) {
+        for y in 0..self.height / 2 {
+            for x in 0..self.width {
+                self.cells
+                    .swap(y * self.width + x, (self.height - 1 - y) * self.width + x);
+            }
+        }
+    }
+}
+
+impl Grid<char> {
+    /// Parse a grid made up only of the chars in `valid`.
+    pub fn parse<'a>(
+        lines: impl IntoIterator<Item = Line<'a>>,
+        valid: &str,
+    ) -> Result<Self, ParseError> {
+        let expected = format!("one of `{}`", valid);
+        Self::parse_with(lines, &expected, |c| valid.contains(c).then_some(c))
+    }
+
+    /// `parse` for a whole day's input.
+    pub fn parse_str(day: u32, input: &str, valid: &str) -> Result<Self, ParseError> {
+        Self::parse(parse::lines(day, input), valid)
+    }
+}
+
+/// Any chars at all, one row per line. Not tied to a day, so errors say day 0.
+impl FromStr for Grid<char> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        Self::parse_with(parse::lines(0, s), "", Some)
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: (usize, usize)) -> &T {
+        assert!(
+            pos.0 < self.width && pos.1 < self.height,
+            "{:?} out of bounds",
+            pos
+        );
+        &self.cells[pos.1 * self.width + pos.0]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, pos: (usize, usize)) -> &mut T {
+        assert!(
+            pos.0 < self.width && pos.1 < self.height,
+            "{:?} out of bounds",
+            pos
+        );
+        &mut self.cells[pos.1 * self.width + pos.0]
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in self.rows() {
+            for c in row {
+                write!(f, "{}", c)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse::lines;
+
+    #[test]
+    fn test_transforms() {
+        let grid: Grid<char> = "ab\ncd\nef".parse().unwrap();
+        assert_eq!(grid, Grid::parse(lines(0, "ab\ncd\nef"), "abcdef").unwrap());
+        assert_eq!((grid.width(), grid.height()), (2, 3));
+        assert_eq!(grid.get((1, 2)), Some(&'f'));
+        assert_eq!(grid.get((2, 0)), None);
+        assert_eq!(grid.get((-1, 0)), None);
+        assert_eq!(grid.transpose().to_string(), "ace\nbdf\n");
+        assert_eq!(grid.rotate_cw().to_string(), "eca\nfdb\n");
+        assert_eq!(grid.rotate_ccw().to_string(), "bdf\nace\n");
+        assert_eq!(grid.column(1).collect::<String>(), "bdf");
+        assert_eq!(
+            grid.padded(1, '.').to_string(),
+            "....\n.ab.\n.cd.\n.ef.\n....\n"
+        );
+    }
+
+    #[test]
+    fn test_neighbors() {
+        let grid = Grid::new(3, 3, 0);
+        assert_eq!(
+            grid.neighbors4((0, 0)).collect::<Vec<_>>(),
+            vec![(1, 0), (0, 1)]
+        );
+        assert_eq!(grid.neighbors4((1, 1)).count(), 4);
+        assert_eq!(grid.neighbors8((1, 1)).count(), 8);
+        assert_eq!(grid.neighbors8((2, 2)).count(), 3);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = Grid::parse(lines(14, "O.#\n.O#\n..x"), "O#.").unwrap_err();
+        assert_eq!((err.line, err.column), (3, 3));
+        let err = Grid::parse(lines(14, "O.#\n.O#.\n"), "O#.").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 4));
+        let err = Grid::parse(lines(14, "O.#\n.O\n"), "O#.").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 3));
+        let err = Grid::parse_str(14, "O.#\n.O!", "O#.").unwrap_err();
+        assert_eq!((err.day, err.line, err.column), (14, 2, 3));
+        let err = "ab\nc".parse::<Grid<char>>().unwrap_err();
+        assert_eq!((err.day, err.line, err.column), (0, 2, 2));
+    }
+}
@@ -0,0 +1,411 @@
+use crate::grid::Grid;
+use crate::viewer::{Frame, Palette, Rgb};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Something drawn over the grid. Cells and heatmaps are applied in the order they were added,
+/// then paths go on top.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Overlay {
+    /// Fill these cells.
+    Cells {
+        cells: Vec<(usize, usize)>,
+        color: Rgb,
+    },
+    /// A line through the middle of each of these cells in turn.
+    Path {
+        points: Vec<(usize, usize)>,
+        color: Rgb,
+    },
+    /// Shade each cell from `low` to `high` by its value, relative to the biggest one.
+    Heatmap {
+        values: HashMap<(usize, usize), u64>,
+        low: Rgb,
+        high: Rgb,
+    },
+}
+
+/// A grid state to save as an image: each char coloured by the palette, then the overlays.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Picture {
+    pub grid: Grid<char>,
+    pub palette: Palette,
+    pub overlays: Vec<Overlay>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageFormat {
+    Ppm,
+    Png,
+    Svg,
+}
+
+impl ImageFormat {
+    /// Guess the format from a file extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "ppm" => Some(ImageFormat::Ppm),
+            "png" => Some(ImageFormat::Png),
+            "svg" => Some(ImageFormat::Svg),
+            _ => None,
+        }
+    }
+}
+
+fn blend(low: Rgb, high: Rgb, t: f64) -> Rgb {
+    let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+    Rgb(mix(low.0, high.0), mix(low.1, high.1), mix(low.2, high.2))
+}
+
+impl Picture {
+    pub fn new(grid: Grid<char>, palette: Palette) -> Self {
+        Self {
+            grid,
+            palette,
+            overlays: vec![],
+        }
+    }
+
+    /// A frame from the viewer, with its highlighted cells as an overlay.
+    pub fn from_frame(frame: &Frame, palette: Palette) -> Self {
+        let mut cells: Vec<_> = frame.highlight.iter().copied().collect();
+        cells.sort_unstable();
+        let color = palette.highlight;
+        Self::new(frame.grid.clone(), palette).with_overlay(Overlay::Cells { cells, color })
+    }
+
+    pub fn with_overlay(mut self, overlay: Overlay) -> Self {
+        self.overlays.push(overlay);
+        self
+    }
+
+    /// The colour of every cell once the cell-filling overlays are applied. Paths are drawn
+    /// separately since they don't line up with cells.
+    fn cell_colors(&self) -> Grid<Rgb> {
+        let mut colors = self.grid.map(|&c| self.palette.color(c));
+        for overlay in &self.overlays {
+            match overlay {
+                Overlay::Cells { cells, color } => {
+                    for &cell in cells {
+                        colors[cell] = *color;
+                    }
+                }
+                Overlay::Heatmap { values, low, high } => {
+                    let max = values.values().copied().max().unwrap_or(0).max(1);
+                    for (&cell, &v) in values {
+                        colors[cell] = blend(*low, *high, v as f64 / max as f64);
+                    }
+                }
+                Overlay::Path { .. } => (),
+            }
+        }
+        colors
+    }
+
+    /// Pixels, `scale` to a side for each cell.
+    pub fn rasterize(&self, scale: usize) -> Raster {
+        let scale = scale.max(1);
+        let colors = self.cell_colors();
+        let mut raster = Raster {
+            width: self.grid.width() * scale,
+            height: self.grid.height() * scale,
+            pixels: vec![Rgb(0, 0, 0); self.grid.width() * self.grid.height() * scale * scale],
+        };
+        for ((x, y), &color) in colors.iter() {
+            raster.fill(x * scale, y * scale, scale, color);
+        }
+
+        let thickness = (scale / 3).max(1);
+        let center = |(x, y): (usize, usize)| (x * scale + scale / 2, y * scale + scale / 2);
+        for overlay in &self.overlays {
+            if let Overlay::Path { points, color } = overlay {
+                for pair in points.windows(2) {
+                    raster.line(center(pair[0]), center(pair[1]), thickness, *color);
+                }
+                if let [only] = points[..] {
+                    raster.line(center(only), center(only), thickness, *color);
+                }
+            }
+        }
+        raster
+    }
+
+    /// An SVG document, `scale` units to a side for each cell. Runs of same-coloured cells in a
+    /// row share a rect to keep the file small.
+    pub fn to_svg(&self, scale: usize) -> String {
+        let scale = scale.max(1);
+        let hex = |c: Rgb| format!("#{:02x}{:02x}{:02x}", c.0, c.1, c.2);
+        let mut out = String::new();
+        writeln!(
+            out,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}" shape-rendering="crispEdges">"#,
+            self.grid.width() * scale,
+            self.grid.height() * scale
+        )
+        .unwrap();
+
+        let colors = self.cell_colors();
+        for (y, row) in colors.rows().enumerate() {
+            let mut x = 0;
+            while x < row.len() {
+                let run = row[x..].iter().take_while(|&&c| c == row[x]).count();
+                writeln!(
+                    out,
+                    r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                    x * scale,
+                    y * scale,
+                    run * scale,
+                    scale,
+                    hex(row[x])
+                )
+                .unwrap();
+                x += run;
+            }
+        }
+
+        for overlay in &self.overlays {
+            if let Overlay::Path { points, color } = overlay {
+                let points: Vec<String> = points
+                    .iter()
+                    .map(|&(x, y)| {
+                        let half = scale as f64 / 2.0;
+                        format!(
+                            "{},{}",
+                            (x * scale) as f64 + half,
+                            (y * scale) as f64 + half
+                        )
+                    })
+                    .collect();
+                writeln!(
+                    out,
+                    r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="{}" stroke-linecap="round" stroke-linejoin="round"/>"#,
+                    points.join(" "),
+                    hex(*color),
+                    (scale as f64 / 3.0).max(1.0)
+                )
+                .unwrap();
+            }
+        }
+        out.push_str("</svg>\n");
+        out
+    }
+
+    pub fn encode(&self, format: ImageFormat, scale: usize) -> Vec<u8> {
+        match format {
+            ImageFormat::Ppm => self.rasterize(scale).to_ppm(),
+            ImageFormat::Png => self.rasterize(scale).to_png(),
+            ImageFormat::Svg => self.to_svg(scale).into_bytes(),
+        }
+    }
+
+    /// Write to `path` in the format its extension asks for.
+    pub fn save(&self, path: &Path, scale: usize) -> io::Result<()> {
+        let format = ImageFormat::from_path(path).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "unknown image format, expected .ppm, .png or .svg",
+            )
+        })?;
+        fs::write(path, self.encode(format, scale))
+    }
+}
+
+/// Row-major pixels.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Raster {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Rgb>,
+}
+
+impl Raster {
+    /// Fill the `size` square with its top left at `(x, y)`, clipped to the raster.
+    fn fill(&mut self, x: usize, y: usize, size: usize, color: Rgb) {
+        for py in y..(y + size).min(self.height) {
+            for px in x..(x + size).min(self.width) {
+                self.pixels[py * self.width + px] = color;
+            }
+        }
+    }
+
+    // Bresenham, stamping a square of side `thickness` centred on each point
+    fn line(&mut self, from: (usize, usize), to: (usize, usize), thickness: usize, color: Rgb) {
+        let (mut x, mut y) = (from.0 as i64, from.1 as i64);
+        let (x1, y1) = (to.0 as i64, to.1 as i64);
+        let (dx, dy) = ((x1 - x).abs(), -(y1 - y).abs());
+        let (sx, sy) = ((x1 - x).signum(), (y1 - y).signum());
+        let mut err = dx + dy;
+        let half = (thickness / 2) as i64;
+        loop {
+            let (px, py) = ((x - half).max(0) as usize, (y - half).max(0) as usize);
+            self.fill(px, py, thickness, color);
+            if x == x1 && y == y1 {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+
+    /// Binary PPM (P6).
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut out = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        for p in &self.pixels {
+            out.extend([p.0, p.1, p.2]);
+        }
+        out
+    }
+
+    /// 8-bit RGB PNG. The image data isn't compressed (stored deflate blocks), which keeps the
+    /// encoder short and dependency free at the cost of file size.
+    pub fn to_png(&self) -> Vec<u8> {
+        let mut raw = Vec::with_capacity(self.height * (1 + 3 * self.width));
+        for row in self.pixels.chunks(self.width.max(1)).take(self.height) {
+            // Filter type 0, none
+            raw.push(0);
+            for p in row {
+                raw.extend([p.0, p.1, p.2]);
+            }
+        }
+
+        let mut ihdr = vec![];
+        ihdr.extend((self.width as u32).to_be_bytes());
+        ihdr.extend((self.height as u32).to_be_bytes());
+        // Bit depth 8, colour type 2 (RGB), default compression, filtering and no interlace
+        ihdr.extend([8, 2, 0, 0, 0]);
+
+        let mut out = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+        png_chunk(&mut out, b"IHDR", &ihdr);
+        png_chunk(&mut out, b"IDAT", &zlib_stored(&raw));
+        png_chunk(&mut out, b"IEND", &[]);
+        out
+    }
+}
+
+fn png_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend((data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend(kind);
+    out.extend(data);
+    let crc = crc32(&out[start..]);
+    out.extend(crc.to_be_bytes());
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+// A zlib stream of uncompressed deflate blocks, which hold at most 65535 bytes each.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(65535).peekable();
+    if blocks.peek().is_none() {
+        out.extend([1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let len = block.len() as u16;
+        out.push(last as u8);
+        out.extend(len.to_le_bytes());
+        out.extend((!len).to_le_bytes());
+        out.extend(block);
+    }
+    out.extend(adler32(data).to_be_bytes());
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn picture() -> Picture {
+        let grid = Grid::from_rows(vec![vec!['#', '.'], vec!['.', '#']]);
+        let palette = Palette::new(&[('#', Rgb(0, 0, 0)), ('.', Rgb(255, 255, 255))]);
+        Picture::new(grid, palette)
+            .with_overlay(Overlay::Cells {
+                cells: vec![(1, 1)],
+                color: Rgb(255, 0, 0),
+            })
+            .with_overlay(Overlay::Path {
+                points: vec![(0, 0), (1, 0)],
+                color: Rgb(0, 0, 255),
+            })
+    }
+
+    #[test]
+    fn test_raster() {
+        let raster = picture().rasterize(3);
+        assert_eq!((raster.width, raster.height), (6, 6));
+        let at = |x: usize, y: usize| raster.pixels[y * 6 + x];
+        assert_eq!(at(0, 0), Rgb(0, 0, 0));
+        assert_eq!(at(1, 1), Rgb(0, 0, 255));
+        assert_eq!(at(4, 1), Rgb(0, 0, 255));
+        assert_eq!(at(0, 4), Rgb(255, 255, 255));
+        assert_eq!(at(4, 4), Rgb(255, 0, 0));
+
+        let ppm = raster.to_ppm();
+        assert!(ppm.starts_with(b"P6\n6 6\n255\n"));
+        assert_eq!(ppm.len(), 11 + 6 * 6 * 3);
+    }
+
+    #[test]
+    fn test_png() {
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+
+        let png = picture().rasterize(2).to_png();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        assert!(png.ends_with(&[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]));
+        // 4 rows of a filter byte and 4 pixels, in one stored block
+        let idat = png.windows(4).position(|w| w == b"IDAT").unwrap();
+        let len = u32::from_be_bytes(png[idat - 4..idat].try_into().unwrap());
+        assert_eq!(len as usize, 2 + 5 + 4 * (1 + 4 * 3) + 4);
+    }
+
+    #[test]
+    fn test_svg() {
+        let svg = picture().to_svg(10);
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains(r##"<rect x="0" y="10" width="10" height="10" fill="#ffffff"/>"##));
+        assert!(svg.contains(r#"points="5,5 15,5""#));
+        assert_eq!(
+            ImageFormat::from_path(Path::new("out/day10.PNG")),
+            Some(ImageFormat::Png)
+        );
+        assert_eq!(ImageFormat::from_path(Path::new("out/day10.gif")), None);
+    }
+}
@@ -3,6 +3,8 @@ extern crate aoc_runner;
 #[macro_use]
 extern crate aoc_runner_derive;
 
+pub mod answers;
+pub mod bench;
 pub mod day1;
 pub mod day10;
 //pub mod day11;
@@ -27,5 +29,45 @@ pub mod day6;
 pub mod day7;
 pub mod day8;
 pub mod day9;
+pub mod direction;
+pub mod grid;
+pub mod image;
+pub mod parse;
+pub mod runner;
+pub mod solution;
+pub mod viewer;
+
+use solution::Day;
+
+/// Every day we have a solution for, in order.
+pub static DAYS: &[Day] = &[
+    Day::new::<day1::Day1>(),
+    Day::new::<day2::Day2>(),
+    Day::new::<day3::Day3>(),
+    Day::new::<day4::Day4>(),
+    Day::new::<day5::Day5>(),
+    Day::new::<day6::Day6>(),
+    Day::new::<day7::Day7>(),
+    Day::new::<day8::Day8>(),
+    Day::new::<day9::Day9>(),
+    Day::new::<day10::Day10>(),
+    Day::new::<day12::Day12>(),
+    Day::new::<day13::Day13>(),
+    Day::new::<day14::Day14>(),
+    Day::new::<day15::Day15>(),
+    Day::new::<day16::Day16>(),
+    Day::new::<day17::Day17>(),
+    Day::new::<day18::Day18>(),
+    Day::new::<day19::Day19>(),
+    Day::new::<day20::Day20>(),
+    Day::new::<day21::Day21>(),
+    Day::new::<day22::Day22>(),
+    Day::new::<day23::Day23>(),
+    Day::new::<day24::Day24>(),
+];
+
+pub fn day(n: u32) -> Option<&'static Day> {
+    DAYS.iter().find(|d| d.day == n)
+}
 
 aoc_lib! { year = 2023 }
@@ -1,3 +1,500 @@
-use aoc_runner_derive::aoc_main;
+use aoc2023::answers::{self, Manifest, Verdict};
+use aoc2023::bench::{self, Settings};
+use aoc2023::image::{ImageFormat, Picture};
+use aoc2023::runner::{self, Format, Record, Source};
+use aoc2023::solution::{Day, Status};
+use aoc2023::viewer::Viewer;
+use clap::{Parser, Subcommand};
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
+use tracing::Level;
+use tracing_subscriber::fmt::format::FmtSpan;
 
-aoc_main! { lib = aoc2023 }
+#[derive(Parser)]
+#[command(about = "Advent of Code 2023")]
+struct Cli {
+    /// Output format for answers and check results.
+    #[arg(long, value_enum, default_value_t = Format::Plain, global = true)]
+    format: Format,
+
+    /// Solver diagnostics on stderr: -v for span timings, -vv for debug, -vvv for trace.
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    verbose: u8,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Solve one day.
+    Run {
+        day: u32,
+        /// Only run this part.
+        #[arg(long, value_parser = clap::value_parser!(u8).range(1..=2))]
+        part: Option<u8>,
+        /// Input file, or `-` for stdin. Defaults to input/2023/dayN.txt.
+        #[arg(long)]
+        input: Option<String>,
+    },
+    /// Solve every day that has an input file.
+    All {
+        /// Also run parts marked as slow.
+        #[arg(long)]
+        slow: bool,
+    },
+    /// Solve every day and compare against the known answers, failing on any mismatch.
+    Verify {
+        /// Answers manifest.
+        #[arg(long, default_value = "answers.toml")]
+        answers: PathBuf,
+        /// Also run parts marked as slow.
+        #[arg(long)]
+        slow: bool,
+    },
+    /// Time parsing and each part, and compare with the previous benchmark.
+    Bench {
+        /// Only benchmark this day.
+        day: Option<u32>,
+        /// Repetitions per stage.
+        #[arg(long, default_value_t = 10)]
+        runs: usize,
+        /// Stop repeating a stage after this many seconds.
+        #[arg(long, default_value_t = 10.0)]
+        budget: f64,
+        /// Also run parts marked as slow.
+        #[arg(long)]
+        slow: bool,
+        /// Timing history, one JSON object per line.
+        #[arg(long, default_value = ".bench_history.jsonl")]
+        history: PathBuf,
+        /// Don't append this run to the history.
+        #[arg(long)]
+        no_save: bool,
+    },
+    /// List the available days and the status of each part.
+    List,
+    /// Animate a day's simulation in the terminal.
+    View {
+        day: u32,
+        /// Input file, or `-` for stdin. Defaults to input/2023/dayN.txt.
+        #[arg(long)]
+        input: Option<String>,
+        /// Milliseconds between frames to start with.
+        #[arg(long, default_value_t = 100)]
+        delay: u64,
+    },
+    /// Save a picture of a day's solution, or of one frame from `view`.
+    Image {
+        day: u32,
+        /// Where to write it. The extension picks the format: .ppm, .png or .svg.
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Input file, or `-` for stdin. Defaults to input/2023/dayN.txt.
+        #[arg(long)]
+        input: Option<String>,
+        /// Pixels to a side for each grid cell.
+        #[arg(long, default_value_t = 4)]
+        scale: usize,
+        /// Draw this frame (counting from 1) of the day's animation instead.
+        #[arg(long)]
+        frame: Option<usize>,
+    },
+    /// Write a Graphviz DOT file of how a day's input is wired up.
+    Graph {
+        day: u32,
+        /// Where to write it. Defaults to stdout.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Input file, or `-` for stdin. Defaults to input/2023/dayN.txt.
+        #[arg(long)]
+        input: Option<String>,
+        /// Colour everything by its state after this many steps, e.g. button presses.
+        #[arg(long)]
+        steps: Option<usize>,
+    },
+    /// Parse inputs without solving, reporting any errors.
+    Check {
+        /// Only check this day.
+        day: Option<u32>,
+        /// Input file, or `-` for stdin. Needs a day.
+        #[arg(long, requires = "day")]
+        input: Option<String>,
+    },
+}
+
+fn init_tracing(verbose: u8) {
+    let level = match verbose {
+        0 => Level::WARN,
+        1 => Level::INFO,
+        2 => Level::DEBUG,
+        _ => Level::TRACE,
+    };
+    let spans = if verbose > 0 {
+        FmtSpan::CLOSE
+    } else {
+        FmtSpan::NONE
+    };
+    tracing_subscriber::fmt()
+        .with_writer(io::stderr)
+        .with_ansi(io::stderr().is_terminal())
+        .with_max_level(level)
+        .with_span_events(spans)
+        .with_target(false)
+        .init();
+}
+
+fn find_day(n: u32) -> Result<&'static Day, String> {
+    aoc2023::day(n).ok_or_else(|| format!("no solution for day {}", n))
+}
+
+fn read_source(day: u32, input: Option<String>) -> Result<Source, String> {
+    let path = input.unwrap_or_else(|| runner::default_input(day).display().to_string());
+    Source::read(&path).map_err(|e| format!("{}: {}", path, e))
+}
+
+/// Records for the `all` subcommand. Days without an input file are skipped with a warning.
+fn run_all(slow: bool) -> Vec<Record> {
+    let mut records = vec![];
+    for day in aoc2023::DAYS {
+        let source = match read_source(day.day, None) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("skipping day {}: {}", day.day, e);
+                continue;
+            }
+        };
+        let parts: Vec<u8> = [1, 2]
+            .into_iter()
+            .filter(|&p| {
+                let status = day.status[p as usize - 1];
+                status != Status::TooSlow && (slow || status != Status::Slow)
+            })
+            .collect();
+        match runner::solve(day, &source, &parts) {
+            Ok(solved) => records.extend(solved),
+            Err(err) => records.push(Record::failed(day, &source, &err)),
+        }
+    }
+    records
+}
+
+/// Slow parts only run when asked for, unimplemented and too slow ones never do.
+fn runnable(day: &Day, part: u8, slow: bool) -> bool {
+    match day.status[part as usize - 1] {
+        Status::Slow => slow,
+        Status::Unimplemented | Status::TooSlow => false,
+        Status::Solved | Status::Hardcoded => true,
+    }
+}
+
+fn verify(manifest: &Manifest, slow: bool) -> Vec<Verdict> {
+    let mut verdicts = vec![];
+    for day in aoc2023::DAYS {
+        let source = match read_source(day.day, None) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("skipping day {}: {}", day.day, e);
+                continue;
+            }
+        };
+        let (parts, skipped): (Vec<u8>, Vec<u8>) =
+            [1, 2].into_iter().partition(|&p| runnable(day, p, slow));
+        match runner::solve(day, &source, &parts) {
+            Ok(records) => verdicts.extend(
+                records
+                    .iter()
+                    .map(|r| Verdict::judge(manifest, r, r.part.unwrap())),
+            ),
+            Err(err) => {
+                let record = Record::failed(day, &source, &err);
+                verdicts.extend(parts.iter().map(|&p| Verdict::judge(manifest, &record, p)));
+            }
+        }
+        for p in skipped {
+            verdicts.push(Verdict::skipped(day.day, p, &source.name, manifest));
+        }
+    }
+    verdicts.sort_by_key(|v| (v.day, v.part));
+    verdicts
+}
+
+fn run_bench(
+    day: Option<u32>,
+    settings: Settings,
+    slow: bool,
+) -> Result<Vec<bench::Entry>, String> {
+    let days: Vec<&Day> = match day {
+        Some(n) => vec![find_day(n)?],
+        None => aoc2023::DAYS.iter().collect(),
+    };
+    let mut entries = vec![];
+    for day in days {
+        let source = match read_source(day.day, None) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("skipping day {}: {}", day.day, e);
+                continue;
+            }
+        };
+        let parts: Vec<u8> = [1, 2]
+            .into_iter()
+            .filter(|&p| runnable(day, p, slow))
+            .collect();
+        match bench::bench(day, &source, &parts, settings) {
+            Ok(timed) => entries.extend(timed),
+            Err(e) => eprintln!("skipping day {}: {}", day.day, e),
+        }
+    }
+    Ok(entries)
+}
+
+fn view(day: u32, input: Option<String>, delay: Duration) -> Result<(), String> {
+    let d = find_day(day)?;
+    let source = read_source(day, input)?;
+    let parsed = d.parse(&source.text).map_err(|e| e.to_string())?;
+    let frames = d.frames(parsed.as_ref());
+    if frames.is_empty() {
+        return Err(format!("day {} has nothing to view", day));
+    }
+    let viewer = Viewer {
+        palette: (d.palette)(),
+        delay,
+    };
+    viewer.play(&frames).map_err(|e| e.to_string())
+}
+
+fn image(
+    day: u32,
+    input: Option<String>,
+    output: &Path,
+    scale: usize,
+    frame: Option<usize>,
+) -> Result<(), String> {
+    if ImageFormat::from_path(output).is_none() {
+        return Err(format!(
+            "{}: unknown image format, expected .ppm, .png or .svg",
+            output.display()
+        ));
+    }
+    let d = find_day(day)?;
+    let source = read_source(day, input)?;
+    let parsed = d.parse(&source.text).map_err(|e| e.to_string())?;
+    let picture = match frame {
+        Some(n) => {
+            let frames = d.frames(parsed.as_ref());
+            let frame = n
+                .checked_sub(1)
+                .and_then(|i| frames.get(i))
+                .ok_or_else(|| format!("day {} has {} frames", day, frames.len()))?;
+            Picture::from_frame(frame, (d.palette)())
+        }
+        None => d
+            .picture(parsed.as_ref())
+            .ok_or_else(|| format!("day {} has nothing to draw", day))?,
+    };
+    picture
+        .save(output, scale)
+        .map_err(|e| format!("{}: {}", output.display(), e))
+}
+
+fn graph(
+    day: u32,
+    input: Option<String>,
+    output: Option<&Path>,
+    steps: Option<usize>,
+) -> Result<(), String> {
+    let d = find_day(day)?;
+    let source = read_source(day, input)?;
+    let parsed = d.parse(&source.text).map_err(|e| e.to_string())?;
+    let dot = d
+        .graph(parsed.as_ref(), steps)
+        .ok_or_else(|| format!("day {} has no graph", day))?;
+    match output {
+        Some(path) => fs::write(path, dot).map_err(|e| format!("{}: {}", path.display(), e)),
+        None => io::stdout()
+            .write_all(dot.as_bytes())
+            .map_err(|e| e.to_string()),
+    }
+}
+
+fn list(format: Format) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    match format {
+        Format::Plain => {
+            for day in aoc2023::DAYS {
+                writeln!(
+                    out,
+                    "day {:>2}: {}, {}",
+                    day.day, day.status[0], day.status[1]
+                )?;
+            }
+        }
+        Format::Json => {
+            let days: Vec<_> = aoc2023::DAYS
+                .iter()
+                .map(|d| serde_json::json!({ "day": d.day, "status": d.status }))
+                .collect();
+            serde_json::to_writer_pretty(&mut out, &days)?;
+            writeln!(out)?;
+        }
+        Format::Tsv => {
+            writeln!(out, "day\tpart1\tpart2")?;
+            for day in aoc2023::DAYS {
+                writeln!(out, "{}\t{}\t{}", day.day, day.status[0], day.status[1])?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    init_tracing(cli.verbose);
+    let records = match cli.command {
+        Command::Run { day, part, input } => {
+            let result = find_day(day).and_then(|d| {
+                let source = read_source(day, input)?;
+                let parts = part.map(|p| vec![p]).unwrap_or_else(|| vec![1, 2]);
+                runner::solve(d, &source, &parts).map_err(|e| e.to_string())
+            });
+            match result {
+                Ok(records) => records,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+        Command::All { slow } => run_all(slow),
+        Command::Verify { answers, slow } => {
+            let manifest = match Manifest::load(&answers) {
+                Ok(manifest) => manifest,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return ExitCode::FAILURE;
+                }
+            };
+            let verdicts = verify(&manifest, slow);
+            let failed = verdicts.iter().any(|v| v.outcome == answers::Outcome::Fail);
+            let written = answers::write_verdicts(&mut io::stdout().lock(), &verdicts, cli.format);
+            return if written.is_err() || failed {
+                ExitCode::FAILURE
+            } else {
+                ExitCode::SUCCESS
+            };
+        }
+        Command::Bench {
+            day,
+            runs,
+            budget,
+            slow,
+            history,
+            no_save,
+        } => {
+            let settings = Settings {
+                runs: runs.max(1),
+                budget: Duration::from_secs_f64(budget),
+            };
+            let result = bench::load_history(&history)
+                .map_err(|e| format!("{}: {}", history.display(), e))
+                .and_then(|previous| Ok((previous, run_bench(day, settings, slow)?)));
+            let (previous, entries) = match result {
+                Ok(result) => result,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return ExitCode::FAILURE;
+                }
+            };
+            if !no_save {
+                if let Err(e) = bench::save_history(&history, &entries) {
+                    eprintln!("{}: {}", history.display(), e);
+                }
+            }
+            let comparisons = bench::compare(&previous, entries);
+            return match bench::write_comparisons(
+                &mut io::stdout().lock(),
+                &comparisons,
+                cli.format,
+            ) {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(_) => ExitCode::FAILURE,
+            };
+        }
+        Command::List => {
+            return match list(cli.format) {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(_) => ExitCode::FAILURE,
+            };
+        }
+        Command::View { day, input, delay } => {
+            return match view(day, input, Duration::from_millis(delay)) {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    ExitCode::FAILURE
+                }
+            };
+        }
+        Command::Image {
+            day,
+            output,
+            input,
+            scale,
+            frame,
+        } => {
+            return match image(day, input, &output, scale, frame) {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    ExitCode::FAILURE
+                }
+            };
+        }
+        Command::Graph {
+            day,
+            output,
+            input,
+            steps,
+        } => {
+            return match graph(day, input, output.as_deref(), steps) {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    ExitCode::FAILURE
+                }
+            };
+        }
+        Command::Check { day, input } => {
+            let days: Result<Vec<&Day>, String> = match day {
+                Some(n) => find_day(n).map(|d| vec![d]),
+                None => Ok(aoc2023::DAYS.iter().collect()),
+            };
+            let days = match days {
+                Ok(days) => days,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return ExitCode::FAILURE;
+                }
+            };
+            let mut records = vec![];
+            for d in days {
+                match read_source(d.day, input.clone()) {
+                    Ok(source) => records.push(runner::check(d, &source)),
+                    Err(e) => eprintln!("skipping day {}: {}", d.day, e),
+                }
+            }
+            records
+        }
+    };
+
+    let failed = records.iter().any(|r| r.error.is_some());
+    if runner::write_records(&mut io::stdout().lock(), &records, cli.format).is_err() || failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
@@ -0,0 +1,207 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Why and where a day's `load_input` gave up on its input. The `Display` impl prints a caret
+/// diagnostic pointing at the offending character, which is what the runner shows.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub day: u32,
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column, in chars.
+    pub column: usize,
+    /// What we were looking for, e.g. "a number" or "`->`".
+    pub expected: String,
+    /// The offending line, empty if we ran out of input.
+    pub text: String,
+}
+
+impl ParseError {
+    pub fn new(day: u32, line: usize, column: usize, expected: &str, text: &str) -> Self {
+        Self {
+            day,
+            line,
+            column,
+            expected: expected.to_string(),
+            text: text.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let gutter = self.line.to_string().len();
+        writeln!(
+            f,
+            "day {}, line {}, column {}: expected {}",
+            self.day, self.line, self.column, self.expected
+        )?;
+        writeln!(f, "{:gutter$} |", "")?;
+        writeln!(f, "{} | {}", self.line, self.text)?;
+        write!(f, "{:gutter$} | {:>col$}", "", "^", col = self.column)
+    }
+}
+
+impl Error for ParseError {}
+
+/// One line of puzzle input. Knows where it came from so errors can point back into it; every
+/// `&str` handed to these helpers should be a slice of `text`.
+#[derive(Clone, Copy, Debug)]
+pub struct Line<'a> {
+    pub day: u32,
+    /// 1-based line number.
+    pub number: usize,
+    pub text: &'a str,
+}
+
+impl<'a> Line<'a> {
+    /// 1-based column of `at` within this line, or just past the end if `at` isn't part of it.
+    pub fn column(&self, at: &str) -> usize {
+        let start = self.text.as_ptr() as usize;
+        let offset = (at.as_ptr() as usize).wrapping_sub(start);
+        let offset = if offset <= self.text.len() && self.text.is_char_boundary(offset) {
+            offset
+        } else {
+            self.text.len()
+        };
+        self.text[..offset].chars().count() + 1
+    }
+
+    pub fn error(&self, at: &str, expected: &str) -> ParseError {
+        ParseError::new(self.day, self.number, self.column(at), expected, self.text)
+    }
+
+    /// Error pointing just past the end of `s`, for when something is missing.
+    pub fn error_after(&self, s: &str, expected: &str) -> ParseError {
+        self.error(&s[s.len()..], expected)
+    }
+
+    pub fn parse<T: FromStr>(&self, token: &'a str, expected: &str) -> Result<T, ParseError> {
+        token.parse::<T>().map_err(|_| self.error(token, expected))
+    }
+
+    /// Whitespace separated numbers.
+    pub fn numbers<T: FromStr>(&self, s: &'a str) -> Result<Vec<T>, ParseError> {
+        s.split_whitespace()
+            .map(|token| self.parse(token, "a number"))
+            .collect()
+    }
+
+    /// `sep` separated values, surrounding whitespace is ignored.
+    pub fn list<T: FromStr>(&self, s: &'a str, sep: char) -> Result<Vec<T>, ParseError> {
+        s.split(sep)
+            .map(|token| self.parse(token.trim(), "a number"))
+            .collect()
+    }
+
+    pub fn split_once(&self, s: &'a str, sep: &str) -> Result<(&'a str, &'a str), ParseError> {
+        s.split_once(sep)
+            .ok_or_else(|| self.error_after(s, &format!("`{}`", sep)))
+    }
+
+    pub fn strip_prefix(&self, s: &'a str, prefix: &str) -> Result<&'a str, ParseError> {
+        s.strip_prefix(prefix)
+            .ok_or_else(|| self.error(s, &format!("`{}`", prefix)))
+    }
+
+    pub fn strip_suffix(&self, s: &'a str, suffix: &str) -> Result<&'a str, ParseError> {
+        s.strip_suffix(suffix)
+            .ok_or_else(|| self.error_after(s, &format!("`{}`", suffix)))
+    }
+
+    /// Every char of `s` must be one of `valid`.
+    pub fn only(&self, s: &'a str, valid: &str) -> Result<&'a str, ParseError> {
+        match s.char_indices().find(|(_, c)| !valid.contains(*c)) {
+            Some((i, _)) => Err(self.error(&s[i..], &format!("one of `{}`", valid))),
+            None => Ok(s),
+        }
+    }
+}
+
+/// Iterator over the lines of a day's input. CRLF line endings are fine.
+#[derive(Clone, Debug)]
+pub struct Lines<'a> {
+    day: u32,
+    number: usize,
+    iter: std::str::Lines<'a>,
+}
+
+impl<'a> Lines<'a> {
+    /// Like `next`, but running out of input is an error.
+    pub fn next_line(&mut self, expected: &str) -> Result<Line<'a>, ParseError> {
+        let number = self.number + 1;
+        self.next()
+            .ok_or_else(|| ParseError::new(self.day, number, 1, expected, ""))
+    }
+}
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = Line<'a>;
+
+    fn next(&mut self) -> Option<Line<'a>> {
+        let text = self.iter.next()?;
+        self.number += 1;
+        Some(Line {
+            day: self.day,
+            number: self.number,
+            text,
+        })
+    }
+}
+
+pub fn lines(day: u32, input: &str) -> Lines<'_> {
+    Lines {
+        day,
+        number: 0,
+        iter: input.lines(),
+    }
+}
+
+/// Groups of lines separated by blank lines.
+pub fn blocks(day: u32, input: &str) -> Vec<Vec<Line<'_>>> {
+    let mut output = vec![vec![]];
+    for line in lines(day, input) {
+        if line.text.trim().is_empty() {
+            output.push(vec![]);
+        } else {
+            output.last_mut().unwrap().push(line);
+        }
+    }
+    output.retain(|block| !block.is_empty());
+    output
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_caret() {
+        let line = lines(9, "1 2 3\r\n4 x 6\r\n").nth(1).unwrap();
+        assert_eq!(line.text, "4 x 6");
+        let err = line.numbers::<i64>(line.text).unwrap_err();
+        assert_eq!((err.line, err.column), (2, 3));
+        assert_eq!(
+            err.to_string(),
+            "day 9, line 2, column 3: expected a number\n  |\n2 | 4 x 6\n  |   ^"
+        );
+    }
+
+    #[test]
+    fn test_missing() {
+        let mut iter = lines(6, "Time: 7");
+        let line = iter.next_line("a `Time:` line").unwrap();
+        let err = line.split_once(line.text, "->").unwrap_err();
+        assert_eq!(err.column, 8);
+        let err = iter.next_line("a `Distance:` line").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 1));
+    }
+
+    #[test]
+    fn test_blocks() {
+        let blocks = blocks(13, "#.\n.#\n\r\n##\n");
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[1][0].number, 4);
+    }
+}
@@ -0,0 +1,223 @@
+use crate::parse::ParseError;
+use crate::solution::{Answer, Day, Status};
+use serde::Serialize;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Where the puzzle input for `day` lives unless told otherwise.
+pub fn default_input(day: u32) -> PathBuf {
+    PathBuf::from(format!("input/2023/day{}.txt", day))
+}
+
+/// 64-bit FNV-1a, printed as hex. Only used to tell input files apart, so it doesn't need to be
+/// cryptographic.
+pub fn input_hash(input: &str) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in input.bytes() {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
+
+/// A puzzle input and where it came from.
+#[derive(Clone, Debug)]
+pub struct Source {
+    /// File path, or `-` for stdin.
+    pub name: String,
+    pub text: String,
+    pub hash: String,
+}
+
+impl Source {
+    pub fn new(name: &str, text: String) -> Self {
+        Self {
+            name: name.to_string(),
+            hash: input_hash(&text),
+            text,
+        }
+    }
+
+    /// Read `path`, or stdin if `path` is `-`.
+    pub fn read(path: &str) -> io::Result<Self> {
+        let text = if path == "-" {
+            let mut text = String::new();
+            io::stdin().read_to_string(&mut text)?;
+            text
+        } else {
+            fs::read_to_string(path)?
+        };
+        Ok(Self::new(path, text))
+    }
+}
+
+/// One line of output: a solved part, or with `part: None` a parse-only check.
+#[derive(Clone, Debug, Serialize)]
+pub struct Record {
+    pub day: u32,
+    pub part: Option<u8>,
+    pub status: Option<Status>,
+    pub answer: Option<Answer>,
+    pub error: Option<String>,
+    pub input: String,
+    pub input_hash: String,
+    pub parse_ns: u64,
+    pub solve_ns: Option<u64>,
+}
+
+impl Record {
+    fn new(day: &Day, source: &Source, parse_time: Duration) -> Self {
+        Self {
+            day: day.day,
+            part: None,
+            status: None,
+            answer: None,
+            error: None,
+            input: source.name.clone(),
+            input_hash: source.hash.clone(),
+            parse_ns: parse_time.as_nanos() as u64,
+            solve_ns: None,
+        }
+    }
+
+    /// Record for an input that didn't parse.
+    pub fn failed(day: &Day, source: &Source, err: &ParseError) -> Self {
+        Self {
+            error: Some(err.to_string()),
+            ..Self::new(day, source, Duration::ZERO)
+        }
+    }
+}
+
+/// Parse `source` once and run each of `parts` on it.
+pub fn solve(day: &Day, source: &Source, parts: &[u8]) -> Result<Vec<Record>, ParseError> {
+    let start = Instant::now();
+    let input = day.parse(&source.text)?;
+    let parse_time = start.elapsed();
+
+    let mut records = vec![];
+    for &part in parts {
+        let start = Instant::now();
+        let answer = day.solve(input.as_ref(), part);
+        let solve_time = start.elapsed();
+        records.push(Record {
+            part: Some(part),
+            status: Some(day.status[part as usize - 1]),
+            answer: Some(answer),
+            solve_ns: Some(solve_time.as_nanos() as u64),
+            ..Record::new(day, source, parse_time)
+        });
+    }
+    Ok(records)
+}
+
+/// Parse `source` without solving anything.
+pub fn check(day: &Day, source: &Source) -> Record {
+    let start = Instant::now();
+    match day.parse(&source.text) {
+        Ok(_) => Record::new(day, source, start.elapsed()),
+        Err(err) => Record::failed(day, source, &err),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    Plain,
+    Json,
+    Tsv,
+}
+
+const TSV_HEADER: &str = "day\tpart\tstatus\tanswer\terror\tinput\tinput_hash\tparse_ns\tsolve_ns";
+
+/// Write `records` to `out`. JSON is a single array so the whole output is one document.
+pub fn write_records(out: &mut impl Write, records: &[Record], format: Format) -> io::Result<()> {
+    match format {
+        Format::Plain => {
+            for r in records {
+                write!(out, "day {:>2}", r.day)?;
+                if let Some(part) = r.part {
+                    write!(out, " part {}", part)?;
+                }
+                match (&r.answer, &r.error) {
+                    (_, Some(err)) => writeln!(out, ": error\n{}", err)?,
+                    (Some(answer), None) => {
+                        write!(out, ": {:<20}", answer.to_string())?;
+                        if let Some(status) = r.status.filter(|s| *s != Status::Solved) {
+                            write!(out, " [{}]", status)?;
+                        }
+                        writeln!(
+                            out,
+                            " parse {:?}, solve {:?}",
+                            Duration::from_nanos(r.parse_ns),
+                            Duration::from_nanos(r.solve_ns.unwrap_or(0))
+                        )?;
+                    }
+                    (None, None) => {
+                        writeln!(out, ": ok, parse {:?}", Duration::from_nanos(r.parse_ns))?
+                    }
+                }
+            }
+        }
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut *out, records)?;
+            writeln!(out)?;
+        }
+        Format::Tsv => {
+            writeln!(out, "{}", TSV_HEADER)?;
+            for r in records {
+                writeln!(out, "{}", tsv_row(r))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn tsv_row(r: &Record) -> String {
+    fn field<T: ToString>(v: &Option<T>) -> String {
+        v.as_ref().map(|v| v.to_string()).unwrap_or_default()
+    }
+    // Diagnostics span several lines, TSV gets just the first
+    let error = r.error.as_ref().and_then(|e| e.lines().next());
+    [
+        r.day.to_string(),
+        field(&r.part),
+        field(&r.status),
+        field(&r.answer),
+        error.unwrap_or_default().to_string(),
+        r.input.clone(),
+        r.input_hash.clone(),
+        r.parse_ns.to_string(),
+        field(&r.solve_ns),
+    ]
+    .join("\t")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_hash() {
+        assert_eq!(input_hash(""), "cbf29ce484222325");
+        assert_eq!(input_hash("a"), "af63dc4c8601ec8c");
+    }
+
+    #[test]
+    fn test_records() {
+        let day = crate::day(9).unwrap();
+        let source = Source::new("09a.txt", "0 3 6 9 12 15\n".to_string());
+        let records = solve(day, &source, &[1, 2]).unwrap();
+        let row = tsv_row(&records[1]);
+        assert!(row.starts_with("9\t2\tsolved\t-3\t\t09a.txt\t"));
+
+        let mut json = vec![];
+        write_records(&mut json, &records[..1], Format::Json).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(json[0]["answer"], 18);
+
+        let record = check(day, &Source::new("-", "0 3 x".to_string()));
+        assert!(record.error.unwrap().contains("column 5"));
+    }
+}
@@ -0,0 +1,234 @@
+use crate::image::Picture;
+use crate::parse::ParseError;
+use crate::viewer::{Frame, Palette};
+use serde::Serialize;
+use std::any::Any;
+use std::fmt;
+use tracing::info_span;
+
+/// What a part returns. Days disagree on integer types, so numbers are all widened to `i128`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(untagged)]
+pub enum Answer {
+    Number(i128),
+    Text(String),
+}
+
+macro_rules! answer_from_int {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Answer {
+                fn from(n: $t) -> Self {
+                    Answer::Number(n as i128)
+                }
+            }
+        )*
+    };
+}
+
+answer_from_int!(i32, i64, i128, u32, u64, usize);
+
+impl From<u128> for Answer {
+    fn from(n: u128) -> Self {
+        i128::try_from(n).map_or_else(|_| Answer::Text(n.to_string()), Answer::Number)
+    }
+}
+
+impl From<String> for Answer {
+    fn from(s: String) -> Self {
+        Answer::Text(s)
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(s: &str) -> Self {
+        Answer::Text(s.to_string())
+    }
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Answer::Number(n) => write!(f, "{}", n),
+            Answer::Text(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+/// How far we trust a part's answer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Solved,
+    /// Returns a constant that was worked out by hand or by an outside script.
+    Hardcoded,
+    /// Returns a placeholder.
+    Unimplemented,
+    /// Correct, but too slow to run routinely.
+    Slow,
+    /// Works on the examples, but never finishes on a real input.
+    #[serde(rename = "too slow")]
+    TooSlow,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Status::Solved => "solved",
+            Status::Hardcoded => "hardcoded",
+            Status::Unimplemented => "unimplemented",
+            Status::Slow => "slow",
+            Status::TooSlow => "too slow",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// One day's puzzle. Each `dayN` module implements this on a unit struct next to its
+/// `aoc_generator`/`aoc` functions, and `lib.rs` lists them all in `DAYS`.
+pub trait Solution {
+    type Input: 'static;
+
+    const DAY: u32;
+    /// Status of part 1 and part 2.
+    const STATUS: [Status; 2] = [Status::Solved; 2];
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError>;
+    fn part1(input: &Self::Input) -> Answer;
+    fn part2(input: &Self::Input) -> Answer;
+
+    /// Steps of the simulation for `aoc2023 view`, if the day has anything worth watching.
+    fn frames(_input: &Self::Input) -> Vec<Frame> {
+        vec![]
+    }
+
+    /// Colours for `frames`.
+    fn palette() -> Palette {
+        Palette::default()
+    }
+
+    /// A still of the solution for `aoc2023 image`, e.g. the path that was found.
+    fn picture(_input: &Self::Input) -> Option<Picture> {
+        None
+    }
+
+    /// Graphviz DOT source showing how the input is wired up, for `aoc2023 graph`. With `steps`,
+    /// run that far first and show the state things are left in.
+    fn graph(_input: &Self::Input, _steps: Option<usize>) -> Option<String> {
+        None
+    }
+}
+
+/// A `Solution` with its input type erased, so every day fits in one table.
+pub struct Day {
+    pub day: u32,
+    pub status: [Status; 2],
+    parse: fn(&str) -> Result<Box<dyn Any>, ParseError>,
+    parts: [fn(&dyn Any) -> Answer; 2],
+    frames: fn(&dyn Any) -> Vec<Frame>,
+    pub palette: fn() -> Palette,
+    picture: fn(&dyn Any) -> Option<Picture>,
+    graph: fn(&dyn Any, Option<usize>) -> Option<String>,
+}
+
+impl Day {
+    pub const fn new<S: Solution>() -> Self {
+        Self {
+            day: S::DAY,
+            status: S::STATUS,
+            parse: parse_any::<S>,
+            parts: [part_any::<S, 1>, part_any::<S, 2>],
+            frames: frames_any::<S>,
+            palette: S::palette,
+            picture: picture_any::<S>,
+            graph: graph_any::<S>,
+        }
+    }
+
+    pub fn parse(&self, input: &str) -> Result<Box<dyn Any>, ParseError> {
+        let _span = info_span!("parse", day = self.day).entered();
+        (self.parse)(input)
+    }
+
+    /// Run `part` (1 or 2) on the output of this day's `parse`.
+    pub fn solve(&self, input: &dyn Any, part: u8) -> Answer {
+        assert!(part == 1 || part == 2, "no part {}", part);
+        let _span = info_span!("solve", day = self.day, part).entered();
+        (self.parts[part as usize - 1])(input)
+    }
+
+    pub fn frames(&self, input: &dyn Any) -> Vec<Frame> {
+        (self.frames)(input)
+    }
+
+    pub fn picture(&self, input: &dyn Any) -> Option<Picture> {
+        (self.picture)(input)
+    }
+
+    pub fn graph(&self, input: &dyn Any, steps: Option<usize>) -> Option<String> {
+        (self.graph)(input, steps)
+    }
+
+    pub fn run(&self, input: &str, part: u8) -> Result<Answer, ParseError> {
+        Ok(self.solve(self.parse(input)?.as_ref(), part))
+    }
+}
+
+impl fmt::Debug for Day {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Day")
+            .field("day", &self.day)
+            .field("status", &self.status)
+            .finish()
+    }
+}
+
+fn parse_any<S: Solution>(input: &str) -> Result<Box<dyn Any>, ParseError> {
+    Ok(Box::new(S::parse(input)?))
+}
+
+fn downcast<S: Solution>(input: &dyn Any) -> &S::Input {
+    input
+        .downcast_ref::<S::Input>()
+        .expect("input was parsed by another day")
+}
+
+fn part_any<S: Solution, const PART: u8>(input: &dyn Any) -> Answer {
+    let input = downcast::<S>(input);
+    if PART == 1 {
+        S::part1(input)
+    } else {
+        S::part2(input)
+    }
+}
+
+fn frames_any<S: Solution>(input: &dyn Any) -> Vec<Frame> {
+    S::frames(downcast::<S>(input))
+}
+
+fn picture_any<S: Solution>(input: &dyn Any) -> Option<Picture> {
+    S::picture(downcast::<S>(input))
+}
+
+fn graph_any<S: Solution>(input: &dyn Any, steps: Option<usize>) -> Option<String> {
+    S::graph(downcast::<S>(input), steps)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{day, DAYS};
+    use std::fs::read_to_string;
+
+    #[test]
+    fn test_registry() {
+        assert!(DAYS.windows(2).all(|w| w[0].day < w[1].day));
+        assert_eq!(day(23).unwrap().status[1], Status::TooSlow);
+        assert!(day(11).is_none());
+
+        let input = read_to_string("input/2023/09a.txt").unwrap();
+        let day9 = day(9).unwrap();
+        assert_eq!(day9.run(&input, 1), Ok(Answer::Number(114)));
+        assert_eq!(day9.run("1 2 x", 2).unwrap_err().column, 5);
+    }
+}
@@ -1,34 +1,21 @@
 use std::collections::{HashMap, HashSet, VecDeque};
 use scan_fmt::scan_fmt;
-use std::io::Write;
+use crate::parse::{self, ParseError};
+use crate::solution::{Answer, Solution};
 
 type Input = usize;
 
-fn print(board: &Vec<Vec<usize>>, breakpoint: bool) {
-    for x in board {
-        for y in x {
-            print!(".");
-        }
-        println!();
-    }
-    println!();
-
-    if breakpoint {
-        // Get user input to continue to next step
-        std::io::stdout().flush();
-        let mut asdf = String::new();
-        std::io::stdin().read_line(&mut asdf);
-    }
-}
+// To watch a simulation, build `viewer::Frame`s from it and return them from `Solution::frames`,
+// then run `aoc2023 view N`.
 
 #[aoc_generator(dayN)]
-pub fn load_input(input: &str) -> Vec<Input> {
+pub fn load_input(input: &str) -> Result<Vec<Input>, ParseError> {
     let mut output = vec![];
-    for line in input.lines() {
-        output.push(line.parse::<Input>().unwrap());
-        //output.push(scan_fmt!(line, "{} {}", char, char).unwrap());
+    for line in parse::lines(N, input) {
+        output.push(line.parse::<Input>(line.text, "a number")?);
+        //let (a, b) = line.split_once(line.text, " ")?;
     }
-    output
+    Ok(output)
 }
 
 #[aoc(dayN, part1)]
@@ -43,6 +30,26 @@ pub fn part2(input: &[Input]) -> usize {
     0
 }
 
+pub struct DayN;
+
+impl Solution for DayN {
+    type Input = Vec<Input>;
+
+    const DAY: u32 = N;
+
+    fn parse(input: &str) -> Result<Vec<Input>, ParseError> {
+        load_input(input)
+    }
+
+    fn part1(input: &Vec<Input>) -> Answer {
+        part1(input).into()
+    }
+
+    fn part2(input: &Vec<Input>) -> Answer {
+        part2(input).into()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -51,14 +58,14 @@ mod test {
     #[test]
     fn test_part1() {
         let input = read_to_string("input/2023/XXa.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part1(&input), 0);
     }
 
     #[test]
     fn test_part2() {
         let input = read_to_string("input/2023/XXa.txt").unwrap();
-        let input = load_input(&input);
+        let input = load_input(&input).unwrap();
         assert_eq!(part2(&input), 0);
     }
 }
@@ -0,0 +1,258 @@
+use crate::grid::Grid;
+use std::collections::HashSet;
+use std::io::{self, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+use termion::event::Key;
+use termion::input::TermRead;
+use termion::raw::IntoRawMode;
+use termion::screen::IntoAlternateScreen;
+use termion::{clear, color, cursor, style};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+/// Which colour each char of a frame is drawn in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Palette {
+    colors: Vec<(char, Rgb)>,
+    /// For chars that aren't in `colors`.
+    pub default: Rgb,
+    /// For highlighted cells, whatever their char.
+    pub highlight: Rgb,
+}
+
+impl Palette {
+    pub fn new(colors: &[(char, Rgb)]) -> Self {
+        Self {
+            colors: colors.to_vec(),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_highlight(mut self, highlight: Rgb) -> Self {
+        self.highlight = highlight;
+        self
+    }
+
+    pub fn color(&self, c: char) -> Rgb {
+        self.colors
+            .iter()
+            .find(|(k, _)| *k == c)
+            .map_or(self.default, |(_, v)| *v)
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            colors: vec![],
+            default: Rgb(200, 200, 200),
+            highlight: Rgb(255, 80, 80),
+        }
+    }
+}
+
+/// One step of a simulation: a grid, some cells to pick out and a line of text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    pub grid: Grid<char>,
+    pub highlight: HashSet<(usize, usize)>,
+    pub caption: String,
+}
+
+impl Frame {
+    pub fn new(grid: Grid<char>) -> Self {
+        Self {
+            grid,
+            highlight: HashSet::new(),
+            caption: String::new(),
+        }
+    }
+
+    pub fn with_highlight(mut self, cells: impl IntoIterator<Item = (usize, usize)>) -> Self {
+        self.highlight.extend(cells);
+        self
+    }
+
+    pub fn with_caption(mut self, caption: impl Into<String>) -> Self {
+        self.caption = caption.into();
+        self
+    }
+}
+
+const MIN_DELAY: Duration = Duration::from_millis(1);
+const MAX_DELAY: Duration = Duration::from_secs(5);
+
+/// Where we are in an animation. Kept apart from the terminal so the key handling can be tested.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Playback {
+    pub index: usize,
+    pub len: usize,
+    pub paused: bool,
+    pub delay: Duration,
+    pub quit: bool,
+}
+
+impl Playback {
+    pub fn new(len: usize, delay: Duration) -> Self {
+        Self {
+            index: 0,
+            len,
+            paused: false,
+            delay: delay.clamp(MIN_DELAY, MAX_DELAY),
+            quit: false,
+        }
+    }
+
+    /// Move on a frame unless paused. Stops on the last frame.
+    pub fn tick(&mut self) {
+        if !self.paused {
+            if self.index + 1 < self.len {
+                self.index += 1;
+            } else {
+                self.paused = true;
+            }
+        }
+    }
+
+    /// Space pauses, arrows (or `,`/`.`) single-step, `+`/`-` change speed, `g`/`G` jump to the
+    /// ends and `q` quits.
+    pub fn key(&mut self, key: Key) {
+        match key {
+            Key::Char(' ') => self.paused = !self.paused,
+            Key::Right | Key::Char('.') | Key::Char('l') => {
+                self.paused = true;
+                self.index = (self.index + 1).min(self.len - 1);
+            }
+            Key::Left | Key::Char(',') | Key::Char('h') => {
+                self.paused = true;
+                self.index = self.index.saturating_sub(1);
+            }
+            Key::Char('+') | Key::Char('=') => self.delay = (self.delay / 2).max(MIN_DELAY),
+            Key::Char('-') => self.delay = (self.delay * 2).min(MAX_DELAY),
+            Key::Home | Key::Char('g') => self.index = 0,
+            Key::End | Key::Char('G') => self.index = self.len - 1,
+            Key::Char('q') | Key::Esc | Key::Ctrl('c') => self.quit = true,
+            _ => (),
+        }
+    }
+}
+
+/// Plays frames in the terminal, see `Playback::key` for the controls.
+#[derive(Clone, Debug)]
+pub struct Viewer {
+    pub palette: Palette,
+    pub delay: Duration,
+}
+
+impl Viewer {
+    pub fn new(palette: Palette) -> Self {
+        Self {
+            palette,
+            delay: Duration::from_millis(100),
+        }
+    }
+
+    pub fn play(&self, frames: &[Frame]) -> io::Result<()> {
+        if frames.is_empty() {
+            return Ok(());
+        }
+        let screen = io::stdout().into_raw_mode()?.into_alternate_screen()?;
+        let mut screen = cursor::HideCursor::from(screen);
+        let mut keys = termion::async_stdin().keys();
+        let mut playback = Playback::new(frames.len(), self.delay);
+
+        while !playback.quit {
+            self.draw(&mut screen, &frames[playback.index], &playback)?;
+            let shown = (playback.index, playback.paused, playback.delay);
+            let deadline = Instant::now() + playback.delay;
+            loop {
+                for key in keys.by_ref() {
+                    playback.key(key?);
+                }
+                if playback.quit || shown != (playback.index, playback.paused, playback.delay) {
+                    break;
+                }
+                if !playback.paused && Instant::now() >= deadline {
+                    playback.tick();
+                    break;
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+        }
+        write!(screen, "{}", style::Reset)?;
+        screen.flush()
+    }
+
+    /// Draw `frame`, cropped to the terminal, with a status line underneath.
+    pub fn draw(&self, out: &mut impl Write, frame: &Frame, playback: &Playback) -> io::Result<()> {
+        let (cols, rows) = termion::terminal_size().unwrap_or((80, 24));
+        let width = frame.grid.width().min(cols as usize);
+        let height = frame.grid.height().min(rows.saturating_sub(1) as usize);
+
+        write!(out, "{}{}", clear::All, cursor::Goto(1, 1))?;
+        let mut last = None;
+        for y in 0..height {
+            for x in 0..width {
+                let c = frame.grid[(x, y)];
+                let rgb = if frame.highlight.contains(&(x, y)) {
+                    self.palette.highlight
+                } else {
+                    self.palette.color(c)
+                };
+                // Only emit a colour change when the colour actually changes
+                if last != Some(rgb) {
+                    write!(out, "{}", color::Fg(color::Rgb(rgb.0, rgb.1, rgb.2)))?;
+                    last = Some(rgb);
+                }
+                write!(out, "{}", c)?;
+            }
+            write!(out, "\r\n")?;
+        }
+        write!(
+            out,
+            "{}{} frame {}/{} {}{:?}  [space] pause [←/→] step [+/-] speed [q] quit",
+            style::Reset,
+            frame.caption,
+            playback.index + 1,
+            playback.len,
+            if playback.paused { "paused " } else { "" },
+            playback.delay,
+        )?;
+        out.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_playback() {
+        let mut p = Playback::new(3, Duration::from_millis(100));
+        p.tick();
+        p.tick();
+        assert_eq!((p.index, p.paused), (2, false));
+        // Holds on the last frame
+        p.tick();
+        assert_eq!((p.index, p.paused), (2, true));
+
+        p.key(Key::Left);
+        p.key(Key::Left);
+        p.key(Key::Left);
+        assert_eq!(p.index, 0);
+        p.key(Key::Char(' '));
+        p.key(Key::Char('+'));
+        assert_eq!((p.paused, p.delay), (false, Duration::from_millis(50)));
+        p.key(Key::Char('q'));
+        assert!(p.quit);
+    }
+
+    #[test]
+    fn test_palette() {
+        let palette = Palette::new(&[('#', Rgb(0, 0, 0))]);
+        assert_eq!(palette.color('#'), Rgb(0, 0, 0));
+        assert_eq!(palette.color('.'), palette.default);
+    }
+}